@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+
+// Source of the bearer token sent with every Sindri request.  The token is requested anew for each
+// request, so implementations backed by a secret manager can rotate keys without a restart.
+#[async_trait]
+pub trait CredentialProvider: Send + Sync {
+    async fn token(&self) -> anyhow::Result<String>;
+}
+
+// The API key from the config, used unless another provider is installed.
+pub struct StaticCredentialProvider {
+    api_key: String,
+}
+
+impl StaticCredentialProvider {
+    pub fn new(api_key: String) -> Self {
+        Self { api_key }
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for StaticCredentialProvider {
+    async fn token(&self) -> anyhow::Result<String> {
+        Ok(self.api_key.clone())
+    }
+}
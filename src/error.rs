@@ -0,0 +1,128 @@
+use crate::prover::SindriTaskStatus;
+use reqwest::StatusCode;
+use reqwest_retry::{default_on_request_failure, Retryable};
+use std::time::Duration;
+
+// Number of characters of an error response body kept in the error message.
+const MAX_ERROR_BODY_CHARS: usize = 1024;
+
+// Errors surfaced by the Sindri client.  The `ProvingService` implementation flattens these into
+// strings at the SDK boundary, where they are labelled with the client's log prefix, while direct
+// callers of `CloudProver` can match on the kind.
+#[derive(Debug, thiserror::Error)]
+pub enum CloudProverError {
+    #[error("status not ok: {status}, body: {}", truncate_body(.body))]
+    HttpStatus { status: StatusCode, body: String },
+    #[error("API key was rejected")]
+    Unauthorized,
+    #[error("failed to obtain API token: {0}")]
+    Credentials(anyhow::Error),
+    #[error("failed to sign request: {0}")]
+    Signing(anyhow::Error),
+    #[error("failed to decode JSON: {0}")]
+    Decode(#[from] serde_json::Error),
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest_middleware::Error),
+    // The response body broke off or could not be decompressed, typically because the connection
+    // dropped mid-stream.
+    #[error("incomplete response body: {0}")]
+    IncompleteBody(#[source] std::io::Error),
+    #[error("failed to reformat verification key: {0}")]
+    VkReformat(#[from] base64::DecodeError),
+    #[error("circuit version mismatch: expected one of {supported:?}, got {actual}")]
+    VersionMismatch {
+        supported: Vec<String>,
+        actual: String,
+    },
+    #[error("circuit type is undefined")]
+    UndefinedCircuitType,
+    #[error("unrecognized Sindri circuit identifier: {0}")]
+    InvalidCircuitSlug(String),
+    #[error("invalid url: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("{0:?} is not usable as a path segment")]
+    InvalidPathSegment(String),
+    #[error("invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("unrecognized timestamp: {0}")]
+    InvalidTimestamp(String),
+    #[error("response body exceeds the limit of {limit} bytes")]
+    ResponseTooLarge { limit: usize },
+    // See `max_input_bytes`.
+    #[error("proof input of {size} bytes exceeds the limit of {limit} bytes")]
+    InputTooLarge { size: usize, limit: usize },
+    #[error("no proof was submitted with reference {reference}")]
+    UnknownReference { reference: String },
+    #[error("task {task_id} has no proof to verify")]
+    ProofUnavailable { task_id: String },
+    #[error("task {task_id} has no verification key")]
+    VkUnavailable { task_id: String },
+    // A proof could not be verified locally at all, as opposed to failing verification.
+    #[error("cannot verify the proof locally: {0}")]
+    LocalVerification(String),
+    #[error("failed to write the proof: {0}")]
+    ProofWrite(#[source] std::io::Error),
+    #[error("the prover has been shut down")]
+    ShutDown,
+    // Requests are refused for `retry_in` after repeated failures, see `circuit_breaker_threshold`.
+    #[error("not sending requests after repeated failures, retrying in {retry_in:?}")]
+    CircuitOpen { retry_in: Duration },
+    // `last_status` is the status of the last successful poll, if any.
+    #[error(
+        "task {task_id} did not finish within {max_wait:?}, last status: {}",
+        describe_status(.last_status)
+    )]
+    TaskTimeout {
+        task_id: String,
+        max_wait: Duration,
+        last_status: Option<SindriTaskStatus>,
+    },
+}
+
+impl CloudProverError {
+    // Whether repeating the same request later could plausibly succeed.  Rate limiting, server
+    // errors and connection problems are transient; everything else (bad input, bad credentials,
+    // unknown resources, version mismatches) fails fast.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CloudProverError::HttpStatus { status, .. } => is_retryable_status(*status),
+            CloudProverError::Transport(e) => {
+                default_on_request_failure(e) == Some(Retryable::Transient)
+            }
+            CloudProverError::IncompleteBody(_) | CloudProverError::CircuitOpen { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+// Classify a Sindri response status.  Shared by the retry middleware and `is_retryable` so that
+// the two never disagree about which failures are worth another attempt.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+fn describe_status(status: &Option<SindriTaskStatus>) -> String {
+    match status {
+        Some(status) => String::from(status.clone()),
+        None => "unknown".to_string(),
+    }
+}
+
+// Shorten an error response body for display; the full body is kept on the error itself.
+fn truncate_body(body: &str) -> String {
+    match body.char_indices().nth(MAX_ERROR_BODY_CHARS) {
+        Some((end, _)) => format!("{}... ({} bytes total)", &body[..end], body.len()),
+        None => body.to_string(),
+    }
+}
+
+impl From<reqwest::Error> for CloudProverError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_body() || e.is_decode() {
+            return CloudProverError::IncompleteBody(std::io::Error::other(e));
+        }
+        CloudProverError::Transport(e.into())
+    }
+}
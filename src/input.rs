@@ -0,0 +1,69 @@
+use crate::prover::{reprocess_prove_input, validate_prove_input};
+use anyhow::Result;
+use prover_darwin_v2::BundleProvingTask;
+use scroll_proving_sdk::prover::{proving_service::ProveRequest, CircuitType};
+use serde::Serialize;
+
+// Proof input built from typed parts rather than a hand-written JSON string.  Construction checks
+// the input against the shape `prove` expects for its circuit type, and also derives the payload
+// actually sent to Sindri, e.g. the unwrapped `batch_proofs` of a bundle.
+pub struct ProveInput {
+    circuit_type: CircuitType,
+    input: String,
+    payload: String,
+}
+
+impl ProveInput {
+    // Input of a chunk proof: the block traces of the chunk, e.g. `prover_darwin_v2::BlockTrace`s.
+    pub fn chunk<T: Serialize>(block_traces: &[T]) -> Result<Self> {
+        Self::new(CircuitType::Chunk, serde_json::to_string(block_traces)?)
+    }
+
+    // Input of a batch proof, e.g. a `prover_darwin_v2::BatchProvingTask`.
+    pub fn batch<T: Serialize>(task: &T) -> Result<Self> {
+        Self::new(CircuitType::Batch, serde_json::to_string(task)?)
+    }
+
+    // Input of a bundle proof.  Sindri is sent the batch proofs only.
+    pub fn bundle(task: &BundleProvingTask) -> Result<Self> {
+        Ok(Self {
+            circuit_type: CircuitType::Bundle,
+            input: serde_json::to_string(task)?,
+            payload: serde_json::to_string(&task.batch_proofs)?,
+        })
+    }
+
+    fn new(circuit_type: CircuitType, input: String) -> Result<Self> {
+        validate_prove_input(circuit_type, &input)?;
+        let payload = reprocess_prove_input(circuit_type, &input)?;
+        Ok(Self {
+            circuit_type,
+            input,
+            payload,
+        })
+    }
+
+    pub fn circuit_type(&self) -> CircuitType {
+        self.circuit_type
+    }
+
+    // The input in the coordinator's format, as carried by `ProveRequest::input`.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    // The input as submitted to Sindri, i.e. the output of `reprocess_prove_input`.
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    // A request proving this input with the given circuit version.
+    pub fn into_request(self, circuit_version: &str, hard_fork_name: &str) -> ProveRequest {
+        ProveRequest {
+            circuit_type: self.circuit_type,
+            circuit_version: circuit_version.to_string(),
+            hard_fork_name: hard_fork_name.to_string(),
+            input: self.input,
+        }
+    }
+}
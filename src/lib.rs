@@ -1,3 +1,11 @@
+pub mod credentials;
+pub mod error;
+pub mod input;
+pub mod metrics;
 pub mod middleware;
 pub mod prover;
+pub mod signing;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod utils;
+pub mod verifier;
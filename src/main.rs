@@ -21,7 +21,7 @@ async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     let cfg = CloudProverConfig::from_file_and_env(args.config_file)?;
     let sdk_config = cfg.sdk_config.clone();
-    let cloud_prover = CloudProver::new(cfg);
+    let cloud_prover = CloudProver::try_new(cfg)?;
     let prover = ProverBuilder::new(sdk_config)
         .with_proving_service(Box::new(cloud_prover))
         .build()
@@ -0,0 +1,30 @@
+use crate::prover::SindriTaskStatus;
+use reqwest::StatusCode;
+use std::time::Duration;
+
+// Hook for exporting client metrics (e.g. to Prometheus).  Every method defaults to a no-op, so
+// implementations only override what they need and an unconfigured client pays nothing.
+pub trait MetricsRecorder: Send + Sync {
+    // Called once per Sindri API call after its final attempt.  `status` is `None` when no HTTP
+    // response was received at all.
+    fn record_request(&self, _method: &str, _status: Option<StatusCode>, _elapsed: Duration) {}
+
+    // Called when a Sindri API call needed more than one attempt to complete.
+    fn record_retries(&self, _method: &str, _retries: u32) {}
+
+    // Called when a response shows a task in a different status than the previous response for
+    // it did.  `elapsed` is the time since the task was first seen in status `from`.
+    fn record_transition(
+        &self,
+        _task_id: &str,
+        _from: &SindriTaskStatus,
+        _to: &SindriTaskStatus,
+        _elapsed: Duration,
+    ) {
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NoopMetricsRecorder;
+
+impl MetricsRecorder for NoopMetricsRecorder {}
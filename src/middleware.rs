@@ -1,18 +1,49 @@
+use crate::error::is_retryable_status;
+use async_compression::tokio::write::GzipEncoder;
+#[cfg(feature = "zstd")]
 use async_compression::tokio::write::ZstdEncoder;
 use async_trait::async_trait;
 use http::Extensions;
-use reqwest::{header::HeaderValue, header::CONTENT_ENCODING, Body, Request, Response};
-use reqwest_middleware::{Middleware, Next, Result};
+use reqwest::{
+    header::HeaderValue, header::CONTENT_ENCODING, header::RETRY_AFTER, Body, Method, Request,
+    Response, StatusCode,
+};
+use reqwest_middleware::{Error, Middleware, Next, Result};
+use reqwest_retry::{default_on_request_failure, Retryable, RetryableStrategy};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncWrite, AsyncWriteExt, DuplexStream};
 use tokio_util::io::ReaderStream;
 
 const BUFFER_SIZE: usize = 4096;
-const MIN_BODY_SIZE: usize = 512;
+// Bodies smaller than this are sent uncompressed unless a different threshold is configured.
+pub const DEFAULT_MIN_BODY_SIZE: usize = 512;
+// Upper bound on how long a single `Retry-After` hint may stall a request.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
 
+#[cfg(feature = "zstd")]
 #[derive(Debug)]
-pub struct ZstdRequestCompressionMiddleware;
+pub struct ZstdRequestCompressionMiddleware {
+    min_body_size: usize,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdRequestCompressionMiddleware {
+    // Compress only bodies of at least `min_body_size` bytes.
+    pub fn new(min_body_size: usize) -> Self {
+        Self { min_body_size }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Default for ZstdRequestCompressionMiddleware {
+    fn default() -> Self {
+        Self::new(DEFAULT_MIN_BODY_SIZE)
+    }
+}
 
+#[cfg(feature = "zstd")]
 #[async_trait]
 impl Middleware for ZstdRequestCompressionMiddleware {
     async fn handle(
@@ -22,47 +53,249 @@ impl Middleware for ZstdRequestCompressionMiddleware {
         next: Next<'_>,
     ) -> Result<Response> {
         // If the request has a body, compress it using zstd.
-        if let Some(bytes) = req
-            .body()
-            .and_then(|b| b.as_bytes())
-            .filter(|b| b.len() >= MIN_BODY_SIZE)
-        {
-            // Create a new request with the same properties.
-            let (method, url, headers, version) = (
-                req.method().clone(),
-                req.url().clone(),
-                req.headers().clone(),
-                req.version(),
-            );
-            let mut new_req = Request::new(method, url);
-            *new_req.headers_mut() = headers;
-            *new_req.version_mut() = version;
-
-            // Swap out the body with a zstd compressed stream of the original.
-            let (writer, reader) = tokio::io::duplex(BUFFER_SIZE);
-            let body_arc = Arc::new(bytes.to_vec());
-            let body_clone = Arc::clone(&body_arc);
-            tokio::spawn(async move {
-                let mut encoder = ZstdEncoder::new(writer);
-                if let Err(e) = encoder.write_all(&body_clone).await {
-                    log::error!("Failed to compress body: {}", e);
-                }
-                let _ = encoder.shutdown().await;
-            });
-            new_req
-                .body_mut()
-                .replace(Body::wrap_stream(ReaderStream::new(reader)));
-
-            // Set the `Content-Encoding: zstd` header.
-            new_req
-                .headers_mut()
-                .insert(CONTENT_ENCODING, HeaderValue::from_static("zstd"));
-
-            // Proceed with the new request.
-            return next.run(new_req, extensions).await;
+        match compressed_request(&req, self.min_body_size, "zstd", ZstdEncoder::new) {
+            Some(new_req) => next.run(new_req, extensions).await,
+            // If no body needs to be compressed, proceed with the original request.
+            None => next.run(req, extensions).await,
+        }
+    }
+}
+
+// Gzip counterpart of `ZstdRequestCompressionMiddleware`, for gateways that reject zstd bodies.
+#[derive(Debug)]
+pub struct GzipRequestCompressionMiddleware {
+    min_body_size: usize,
+}
+
+impl GzipRequestCompressionMiddleware {
+    // Compress only bodies of at least `min_body_size` bytes.
+    pub fn new(min_body_size: usize) -> Self {
+        Self { min_body_size }
+    }
+}
+
+impl Default for GzipRequestCompressionMiddleware {
+    fn default() -> Self {
+        Self::new(DEFAULT_MIN_BODY_SIZE)
+    }
+}
+
+#[async_trait]
+impl Middleware for GzipRequestCompressionMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        match compressed_request(&req, self.min_body_size, "gzip", GzipEncoder::new) {
+            Some(new_req) => next.run(new_req, extensions).await,
+            None => next.run(req, extensions).await,
         }
+    }
+}
+
+// Build a copy of `req` whose body is streamed through the encoder returned by `make_encoder`, with
+// `Content-Encoding` set to `encoding`.  Returns `None` when the body is absent, streamed,
+// shorter than `min_body_size`, or already encoded.
+fn compressed_request<W, F>(
+    req: &Request,
+    min_body_size: usize,
+    encoding: &'static str,
+    make_encoder: F,
+) -> Option<Request>
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+    F: FnOnce(DuplexStream) -> W,
+{
+    if req.headers().contains_key(CONTENT_ENCODING) {
+        return None;
+    }
+    let bytes = req
+        .body()
+        .and_then(|b| b.as_bytes())
+        .filter(|b| b.len() >= min_body_size)?;
+
+    // Create a new request with the same properties.
+    let (method, url, headers, version) = (
+        req.method().clone(),
+        req.url().clone(),
+        req.headers().clone(),
+        req.version(),
+    );
+    let mut new_req = Request::new(method, url);
+    *new_req.headers_mut() = headers;
+    *new_req.version_mut() = version;
+
+    // Swap out the body with a compressed stream of the original.
+    let (writer, reader) = tokio::io::duplex(BUFFER_SIZE);
+    let body = bytes.to_vec();
+    let mut encoder = make_encoder(writer);
+    tokio::spawn(async move {
+        if let Err(e) = encoder.write_all(&body).await {
+            log::error!("Failed to compress body: {}", e);
+        }
+        let _ = encoder.shutdown().await;
+    });
+    new_req
+        .body_mut()
+        .replace(Body::wrap_stream(ReaderStream::new(reader)));
+
+    // Set the `Content-Encoding` header.
+    new_req
+        .headers_mut()
+        .insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+
+    Some(new_req)
+}
 
-        // If no body needs to be compressed, proceed with the original request.
+// Attached to a request as an extension; counts how many times the request was sent.
+#[derive(Clone, Debug, Default)]
+pub struct AttemptCounter(Arc<AtomicU32>);
+
+impl AttemptCounter {
+    pub fn attempts(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Must be registered after the retry middleware so that it observes every individual attempt.
+#[derive(Debug)]
+pub struct AttemptCountMiddleware;
+
+#[async_trait]
+impl Middleware for AttemptCountMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        if let Some(counter) = extensions.get::<AttemptCounter>() {
+            counter.0.fetch_add(1, Ordering::Relaxed);
+        }
         next.run(req, extensions).await
     }
 }
+
+// Retry rate limiting (429), request timeouts (408) and server errors (5xx); fail fast on every
+// other client error so that bad input or credentials surface immediately.
+#[derive(Debug)]
+pub struct SindriRetryableStrategy;
+
+impl RetryableStrategy for SindriRetryableStrategy {
+    fn handle(&self, res: &std::result::Result<Response, Error>) -> Option<Retryable> {
+        match res {
+            Ok(response) if response.status().is_success() => None,
+            Ok(response) if is_retryable_status(response.status()) => Some(Retryable::Transient),
+            Ok(_) => Some(Retryable::Fatal),
+            Err(error) => default_on_request_failure(error),
+        }
+    }
+}
+
+// Parse a `Retry-After` header value, given either as delay-seconds or as an HTTP-date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+// Hands POST requests to `post` and all others to `other`, e.g. to retry submissions, which are
+// not idempotent, differently from reads.
+#[derive(Debug)]
+pub struct PostOrOtherMiddleware<M> {
+    post: M,
+    other: M,
+}
+
+impl<M> PostOrOtherMiddleware<M> {
+    pub fn new(post: M, other: M) -> Self {
+        Self { post, other }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware> Middleware for PostOrOtherMiddleware<M> {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let middleware = if req.method() == Method::POST {
+            &self.post
+        } else {
+            &self.other
+        };
+        middleware.handle(req, extensions, next).await
+    }
+}
+
+// Waits out the server's `Retry-After` hint on 429/503 responses before handing them back to the
+// retry middleware, so the next attempt happens no earlier than Sindri asked.  Must be registered
+// after `AttemptCountMiddleware` so that it can skip the wait when no attempts remain.
+#[derive(Debug)]
+pub struct RetryAfterMiddleware {
+    max_retries: u32,
+    log_prefix: String,
+}
+
+impl RetryAfterMiddleware {
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            log_prefix: "[Sindri client]".to_string(),
+        }
+    }
+
+    // Log the waits under `prefix`, e.g. that of the prover the client belongs to.
+    pub fn with_log_prefix(mut self, prefix: &str) -> Self {
+        self.log_prefix = prefix.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for RetryAfterMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let attempts = extensions
+            .get::<AttemptCounter>()
+            .map(AttemptCounter::attempts)
+            .unwrap_or(0);
+        let response = next.run(req, extensions).await?;
+
+        let status = response.status();
+        if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+            return Ok(response);
+        }
+        if attempts > self.max_retries {
+            return Ok(response);
+        }
+        if let Some(delay) = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after)
+        {
+            let delay = delay.min(MAX_RETRY_AFTER);
+            log::info!(
+                "{}, {status}, honoring Retry-After of {delay:?}",
+                self.log_prefix
+            );
+            tokio::time::sleep(delay).await;
+        }
+        Ok(response)
+    }
+}
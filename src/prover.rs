@@ -1,16 +1,42 @@
+use crate::credentials::{CredentialProvider, StaticCredentialProvider};
+use crate::error::{is_retryable_status, CloudProverError};
+use crate::metrics::{MetricsRecorder, NoopMetricsRecorder};
+#[cfg(feature = "zstd")]
 use crate::middleware::ZstdRequestCompressionMiddleware;
+use crate::middleware::{
+    AttemptCountMiddleware, AttemptCounter, GzipRequestCompressionMiddleware,
+    PostOrOtherMiddleware, RetryAfterMiddleware, SindriRetryableStrategy, DEFAULT_MIN_BODY_SIZE,
+};
+use crate::signing::RequestSigner;
 use async_trait::async_trait;
 use core::time::Duration;
-use reqwest::{header::CONTENT_TYPE, Url};
+use futures::StreamExt;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE},
+    Method, StatusCode, Url,
+};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio_util::io::{StreamReader, SyncIoBridge};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use retry_policies::Jitter;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Instant, SystemTime};
 
-use crate::utils::proving_timestamps_from_response;
+use crate::utils::{
+    expand_env_vars, from_reader_unbounded, from_str_unbounded, parse_timestamp,
+    proving_timestamps_from_response, ProvingStats,
+};
+use chrono::{DateTime, Utc};
 use scroll_proving_sdk::{
     config::Config as SdkConfig,
     prover::{
@@ -22,14 +48,366 @@ use scroll_proving_sdk::{
     },
 };
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// Run `$fut` inside the `tracing` span `$span` when the `tracing` feature is enabled.  Without the
+// feature the span expression is not compiled at all, so `log`-only users do not depend on tracing.
+macro_rules! traced {
+    ($span:expr, $fut:expr) => {{
+        #[cfg(feature = "tracing")]
+        let fut = {
+            let span = $span;
+            tracing::Instrument::instrument($fut, span)
+        };
+        #[cfg(not(feature = "tracing"))]
+        let fut = $fut;
+        fut
+    }};
+}
+
+// Emit a `tracing` debug event when the `tracing` feature is enabled.
+macro_rules! trace_event {
+    ($($event:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($event)+);
+    };
+}
+
+// Attach the id of a submitted task to the enclosing `sindri.prove` span.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn record_task_id(task_id: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("task_id", task_id);
+    trace_event!("proof submitted");
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct CloudProverConfig {
     pub sdk_config: SdkConfig,
     pub base_url: String,
+    // Path of the Sindri API below `base_url`, e.g. when a proxy rewrites paths.
+    #[serde(default = "default_api_path")]
+    pub api_path: String,
+    // May be omitted when `api_key_file` is set.
+    #[serde(default)]
     pub api_key: String,
+    // File holding the API key, e.g. a mounted secret, read when the config is loaded or built.
+    // Overrides `api_key`, and is itself overridden by `PROVING_SERVICE_API_KEY` in
+    // `from_file_and_env`.
+    #[serde(default)]
+    pub api_key_file: Option<String>,
     pub retry_count: u32,
+    // Overrides `retry_count` for POST requests, i.e. prove submissions, which could start a
+    // duplicate proof if an attempt Sindri accepted is retried.  Submissions carry an idempotency
+    // key against that; setting this to zero guards against gateways that drop it.
+    #[serde(default)]
+    pub post_retry_count: Option<u32>,
     pub retry_wait_time_sec: u64,
+    // How retry waits are randomized so that a fleet of provers does not retry in lockstep.
+    #[serde(default)]
+    pub retry_jitter: RetryJitter,
+    // Lets the exponential backoff grow beyond `retry_wait_time_sec`, up to this many seconds.
+    #[serde(default)]
+    pub max_retry_interval_sec: Option<u64>,
     pub connection_timeout_sec: u64,
+    // Upper bound on establishing the TCP/TLS connection, separate from the total request timeout
+    // so that a dead host fails fast instead of consuming the retry budget.
+    #[serde(default = "default_connect_timeout_sec")]
+    pub connect_timeout_sec: u64,
+    // Idle connections kept open per host for reuse; reqwest's default (unbounded) when unset.
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+    // How long an idle pooled connection is kept open; reqwest's default (90s) when unset.
+    #[serde(default)]
+    pub pool_idle_timeout_sec: Option<u64>,
+    // Requests sent to Sindri at the same time, across clones of the prover; further requests wait
+    // for one of them to complete.  Unlimited when unset.
+    #[serde(default)]
+    pub max_in_flight_requests: Option<usize>,
+    // Consecutive failed requests (connection errors, rate limiting and server errors, each after
+    // its retries) after which requests fail with `CircuitOpen` for `circuit_breaker_cooldown_sec`,
+    // across clones of the prover.  A single trial request is then let through, closing the
+    // breaker if it succeeds.  No breaker when unset.
+    #[serde(default)]
+    pub circuit_breaker_threshold: Option<u32>,
+    #[serde(default = "default_circuit_breaker_cooldown_sec")]
+    pub circuit_breaker_cooldown_sec: u64,
+    // Overrides `connection_timeout_sec` for specific Sindri methods, e.g. `prove` or `detail`.
+    #[serde(default)]
+    pub per_method_timeout_sec: HashMap<String, u64>,
+    #[serde(default = "default_circuit_version")]
+    pub circuit_version: String,
+    // Sindri account or organization under which the circuits are published.
+    #[serde(default = "default_circuit_namespace")]
+    pub circuit_namespace: String,
+    // Additional circuit versions served alongside `circuit_version`, e.g. during a hard fork.
+    #[serde(default)]
+    pub supported_circuit_versions: Vec<String>,
+    // Whether requests for a circuit version other than the above fail.  When off, they are sent
+    // with the requested version after logging a warning, for upgrades where Sindri is known to
+    // serve a version this config does not list yet.
+    #[serde(default = "default_strict_version_check")]
+    pub strict_version_check: bool,
+    // How long a fetched verification key is reused before it is fetched again.  Keys are cached
+    // for the lifetime of the prover when unset.
+    #[serde(default)]
+    pub vk_cache_ttl_sec: Option<u64>,
+    // Whether Sindri verifies each proof after generating it.  Scroll verifies proofs on-chain
+    // anyway, so operators may turn this off to save latency and cost.
+    #[serde(default = "default_perform_verify")]
+    pub perform_verify: bool,
+    // Name of the prove request field carrying the proof input, for Sindri API revisions that
+    // rename it.
+    #[serde(default = "default_proof_input_field")]
+    pub proof_input_field: String,
+    // Added to the body of every prove request, overriding `perform_verify` if present.  An escape
+    // hatch for fields required by Sindri API revisions this crate does not know about yet.
+    #[serde(default)]
+    pub extra_prove_fields: serde_json::Map<String, serde_json::Value>,
+    // Number of submitted inputs remembered so that `query_task` can echo them back.  The oldest
+    // input is forgotten once the limit is reached; zero disables remembering inputs.
+    #[serde(default)]
+    pub input_echo_capacity: usize,
+    // Sent with every request, e.g. tenant tags or keys required by a gateway in front of Sindri.
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+    // Abort reading a response whose decompressed body exceeds this many bytes, protecting the
+    // prover from running out of memory on a pathological response.  Unlimited when unset.
+    #[serde(default)]
+    pub max_response_bytes: Option<usize>,
+    // Submitting the same circuit and input again within this many seconds of a successful
+    // submission returns the earlier task instead of starting a duplicate proof.  A submission
+    // made while the same one is in flight waits for it.  Disabled when unset.
+    #[serde(default)]
+    pub prove_dedup_window_sec: Option<u64>,
+    // Proof inputs larger than this many bytes, as submitted and before compression, are refused
+    // without being sent, rather than sent in a single request that a gateway may reject.
+    // Unlimited when unset.
+    #[serde(default)]
+    pub max_input_bytes: Option<usize>,
+    // Whether response bodies are logged at debug level.  They may carry proofs running to
+    // megabytes, or details best kept out of logs.
+    #[serde(default = "default_log_bodies")]
+    pub log_bodies: bool,
+    // Logged bodies are cut off after this many characters; unlimited when set to null.
+    #[serde(default = "default_log_body_max_chars")]
+    pub log_body_max_chars: Option<usize>,
+    // Label of this prover in its log lines, e.g. `batch-v0.13.1`, which are then prefixed
+    // `[Sindri client:batch-v0.13.1]` to tell apart several provers logging to one process.  Lines
+    // logged outside a prover, e.g. while parsing responses, keep the plain prefix.
+    #[serde(default)]
+    pub log_label: Option<String>,
+    #[serde(default)]
+    pub request_compression: RequestCompression,
+    // Ask Sindri for zstd-compressed responses.  Turning this off (together with setting
+    // `request_compression` to `none`) keeps the traffic readable for intercepting proxies.
+    // Defaults to off without the `zstd` feature, which is then required to turn it on.
+    #[serde(default = "default_response_zstd")]
+    pub response_zstd: bool,
+    // Response encodings to accept, most preferred first, e.g. `["br", "zstd", "gzip"]`.
+    // Overrides `response_zstd` when set; an empty list asks for uncompressed responses.
+    #[serde(default)]
+    pub accept_encodings: Option<Vec<ResponseEncoding>>,
+    // Request bodies shorter than this many bytes are sent uncompressed.
+    #[serde(default = "default_compression_min_body_size")]
+    pub compression_min_body_size: usize,
+    // Run all local processing for `prove` but never submit to Sindri; responses carry
+    // `DRY_RUN_TASK_ID` as their task id.
+    #[serde(default)]
+    pub dry_run: bool,
+    // Route all Sindri traffic through this HTTP(S) proxy.  When unset, the standard
+    // `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` environment variables are honoured.
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    // Defaults to `sindri-scroll-sdk/<crate version> (circuit <circuit_version>)`.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub proxy_username: Option<String>,
+    #[serde(default)]
+    pub proxy_password: Option<String>,
+    // PEM certificate and PKCS#8 PEM private key presented to gateways requiring mutual TLS.  Both
+    // must be set together.
+    #[serde(default)]
+    pub client_cert_path: Option<String>,
+    #[serde(default)]
+    pub client_key_path: Option<String>,
+}
+
+// Randomization applied to each retry wait.  `Full` waits anywhere between zero and the computed
+// backoff, `Equal` between half of `retry_wait_time_sec` and the computed backoff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryJitter {
+    #[default]
+    Full,
+    Equal,
+    None,
+}
+
+impl From<RetryJitter> for Jitter {
+    fn from(jitter: RetryJitter) -> Self {
+        match jitter {
+            RetryJitter::Full => Jitter::Full,
+            RetryJitter::Equal => Jitter::Bounded,
+            RetryJitter::None => Jitter::None,
+        }
+    }
+}
+
+// Encoding applied to request bodies sent to Sindri.  Zstd requires the `zstd` feature, and is
+// the default when it is enabled; gzip is otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestCompression {
+    #[cfg_attr(feature = "zstd", default)]
+    Zstd,
+    #[cfg_attr(not(feature = "zstd"), default)]
+    Gzip,
+    None,
+}
+
+// Encoding Sindri, or a CDN in front of it, may apply to responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseEncoding {
+    // Requires the `zstd` feature.
+    Zstd,
+    Gzip,
+    Deflate,
+    // Brotli; requires the `brotli` feature.
+    Br,
+}
+
+impl ResponseEncoding {
+    // Name of the encoding in `Accept-Encoding`.
+    fn token(self) -> &'static str {
+        match self {
+            ResponseEncoding::Zstd => "zstd",
+            ResponseEncoding::Gzip => "gzip",
+            ResponseEncoding::Deflate => "deflate",
+            ResponseEncoding::Br => "br",
+        }
+    }
+}
+
+impl RequestCompression {
+    // Value of the `Content-Encoding` header of a body in this encoding.
+    fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            RequestCompression::Zstd => Some("zstd"),
+            RequestCompression::Gzip => Some("gzip"),
+            RequestCompression::None => None,
+        }
+    }
+}
+
+// Written by hand so that logging the config never leaks the API key.
+impl std::fmt::Debug for CloudProverConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CloudProverConfig")
+            .field("sdk_config", &self.sdk_config)
+            .field("base_url", &self.base_url)
+            .field("api_path", &self.api_path)
+            .field("api_key", &"***")
+            .field("api_key_file", &self.api_key_file)
+            .field("retry_count", &self.retry_count)
+            .field("post_retry_count", &self.post_retry_count)
+            .field("retry_wait_time_sec", &self.retry_wait_time_sec)
+            .field("retry_jitter", &self.retry_jitter)
+            .field("max_retry_interval_sec", &self.max_retry_interval_sec)
+            .field("connection_timeout_sec", &self.connection_timeout_sec)
+            .field("connect_timeout_sec", &self.connect_timeout_sec)
+            .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+            .field("pool_idle_timeout_sec", &self.pool_idle_timeout_sec)
+            .field("max_in_flight_requests", &self.max_in_flight_requests)
+            .field("circuit_breaker_threshold", &self.circuit_breaker_threshold)
+            .field(
+                "circuit_breaker_cooldown_sec",
+                &self.circuit_breaker_cooldown_sec,
+            )
+            .field("per_method_timeout_sec", &self.per_method_timeout_sec)
+            .field("circuit_version", &self.circuit_version)
+            .field("circuit_namespace", &self.circuit_namespace)
+            .field(
+                "supported_circuit_versions",
+                &self.supported_circuit_versions,
+            )
+            .field("strict_version_check", &self.strict_version_check)
+            .field("vk_cache_ttl_sec", &self.vk_cache_ttl_sec)
+            .field("perform_verify", &self.perform_verify)
+            .field("proof_input_field", &self.proof_input_field)
+            .field("extra_prove_fields", &self.extra_prove_fields)
+            .field("input_echo_capacity", &self.input_echo_capacity)
+            .field("extra_headers", &self.extra_headers.keys())
+            .field("max_response_bytes", &self.max_response_bytes)
+            .field("prove_dedup_window_sec", &self.prove_dedup_window_sec)
+            .field("max_input_bytes", &self.max_input_bytes)
+            .field("log_bodies", &self.log_bodies)
+            .field("log_body_max_chars", &self.log_body_max_chars)
+            .field("log_label", &self.log_label)
+            .field("request_compression", &self.request_compression)
+            .field("response_zstd", &self.response_zstd)
+            .field("accept_encodings", &self.accept_encodings)
+            .field("compression_min_body_size", &self.compression_min_body_size)
+            .field("dry_run", &self.dry_run)
+            .field("proxy_url", &self.proxy_url)
+            .field("user_agent", &self.user_agent)
+            .field("proxy_username", &self.proxy_username)
+            .field(
+                "proxy_password",
+                &self.proxy_password.as_ref().map(|_| "***"),
+            )
+            .field("client_cert_path", &self.client_cert_path)
+            .field("client_key_path", &self.client_key_path)
+            .finish()
+    }
+}
+
+fn default_api_path() -> String {
+    SINDRI_API_PATH.to_string()
+}
+
+fn default_circuit_version() -> String {
+    DEFAULT_CIRCUIT_VERSION.to_string()
+}
+
+fn default_circuit_namespace() -> String {
+    DEFAULT_CIRCUIT_NAMESPACE.to_string()
+}
+
+fn default_connect_timeout_sec() -> u64 {
+    DEFAULT_CONNECT_TIMEOUT_SEC
+}
+
+fn default_compression_min_body_size() -> usize {
+    DEFAULT_MIN_BODY_SIZE
+}
+
+fn default_response_zstd() -> bool {
+    cfg!(feature = "zstd")
+}
+
+fn default_perform_verify() -> bool {
+    true
+}
+
+fn default_circuit_breaker_cooldown_sec() -> u64 {
+    DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SEC
+}
+
+fn default_strict_version_check() -> bool {
+    true
+}
+
+fn default_proof_input_field() -> String {
+    "proof_input".to_string()
+}
+
+fn default_log_bodies() -> bool {
+    true
+}
+
+fn default_log_body_max_chars() -> Option<usize> {
+    Some(DEFAULT_LOG_BODY_MAX_CHARS)
 }
 
 impl CloudProverConfig {
@@ -40,9 +418,39 @@ impl CloudProverConfig {
         serde_json::from_reader(reader).map_err(|e| anyhow!(e))
     }
 
+    pub fn from_toml_reader<R>(mut reader: R) -> Result<Self>
+    where
+        R: std::io::Read,
+    {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        toml::from_str(&contents).map_err(|e| anyhow!(e))
+    }
+
+    pub fn from_yaml_reader<R>(reader: R) -> Result<Self>
+    where
+        R: std::io::Read,
+    {
+        serde_yaml::from_reader(reader).map_err(|e| anyhow!(e))
+    }
+
+    // Parse the config according to the file extension; anything other than `.toml`, `.yaml` or
+    // `.yml` is read as JSON.  The API key is then read from `api_key_file`, if set.
     pub fn from_file(file_name: String) -> Result<Self> {
-        let file = File::open(file_name)?;
-        Self::from_reader(&file)
+        Ok(Self::parse_file(file_name)?.load_api_key_file()?)
+    }
+
+    fn parse_file(file_name: String) -> Result<Self> {
+        let extension = Path::new(&file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        let file = File::open(&file_name)?;
+        match extension.as_deref() {
+            Some("toml") => Self::from_toml_reader(&file),
+            Some("yaml" | "yml") => Self::from_yaml_reader(&file),
+            _ => Self::from_reader(&file),
+        }
     }
 
     fn get_env_var(key: &str) -> Result<Option<String>> {
@@ -55,8 +463,12 @@ impl CloudProverConfig {
             .transpose()
     }
 
+    // Same as `from_file`, with `${VAR}` references expanded (see `expand_env_vars`) and
+    // then the dedicated environment variables applied on top.
     pub fn from_file_and_env(file_name: String) -> Result<Self> {
-        let mut cfg = Self::from_file(file_name)?;
+        let mut cfg = Self::parse_file(file_name)?
+            .expand_env_vars()?
+            .load_api_key_file()?;
         cfg.sdk_config.override_with_env()?;
 
         if let Some(val) = Self::get_env_var("PROVING_SERVICE_BASE_URL")? {
@@ -67,225 +479,2060 @@ impl CloudProverConfig {
             cfg.api_key = val;
         }
 
+        if let Some(val) = Self::get_env_var("PROVING_SERVICE_CIRCUIT_VERSION")? {
+            cfg.circuit_version = val;
+        }
+
+        Ok(cfg)
+    }
+
+    // Replace `${VAR}` references in every string value of the config, including the SDK config,
+    // with the values of the environment variables, e.g. `"api_key": "${SINDRI_API_KEY}"`.
+    pub fn expand_env_vars(self) -> Result<Self, CloudProverError> {
+        let invalid = |e: serde_json::Error| CloudProverError::InvalidConfig(e.to_string());
+        let mut value = serde_json::to_value(self).map_err(invalid)?;
+        expand_env_vars_in(&mut value)?;
+        serde_json::from_value(value).map_err(invalid)
+    }
+
+    // Replace `api_key` with the contents of `api_key_file`, if set, without trailing whitespace
+    // such as the newline secret files usually end with.
+    pub fn load_api_key_file(mut self) -> Result<Self, CloudProverError> {
+        if let Some(path) = &self.api_key_file {
+            let key = std::fs::read_to_string(path).map_err(|e| {
+                CloudProverError::InvalidConfig(format!("cannot read api_key_file {path}: {e}"))
+            })?;
+            self.api_key = key.trim_end().to_string();
+        }
+        Ok(self)
+    }
+
+    // Start building a config in code rather than loading it from a file.
+    pub fn builder() -> CloudProverConfigBuilder {
+        CloudProverConfigBuilder::default()
+    }
+
+    // Wrap an `SdkConfig` the caller already has, with every Sindri-specific setting at its
+    // `builder` default.
+    pub fn from_sdk_config(
+        sdk_config: SdkConfig,
+        base_url: &str,
+        api_key: &str,
+    ) -> Result<Self, CloudProverError> {
+        Self::builder()
+            .sdk_config(sdk_config)
+            .base_url(base_url)
+            .api_key(api_key)
+            .build()
+    }
+
+    // Reject settings that would make every request fail or behave degenerately, and warn about
+    // combinations that are accepted but probably not what was intended.
+    pub fn validate(&self) -> Result<(), CloudProverError> {
+        let invalid = |msg: String| Err(CloudProverError::InvalidConfig(msg));
+        if self.connection_timeout_sec == 0 {
+            return invalid("connection_timeout_sec must be greater than zero".to_string());
+        }
+        if self.connect_timeout_sec == 0 {
+            return invalid("connect_timeout_sec must be greater than zero".to_string());
+        }
+        if self.max_in_flight_requests == Some(0) {
+            return invalid("max_in_flight_requests must be greater than zero".to_string());
+        }
+        if self.circuit_breaker_threshold == Some(0) {
+            return invalid("circuit_breaker_threshold must be greater than zero".to_string());
+        }
+        if self.max_input_bytes == Some(0) {
+            return invalid("max_input_bytes must be greater than zero".to_string());
+        }
+        if let Some((method, _)) = self
+            .per_method_timeout_sec
+            .iter()
+            .find(|(_, sec)| **sec == 0)
+        {
+            return invalid(format!(
+                "per_method_timeout_sec for `{method}` must be greater than zero"
+            ));
+        }
+        self.extra_header_map()?;
+        if cfg!(not(feature = "brotli"))
+            && self.response_encodings().contains(&ResponseEncoding::Br)
+        {
+            return invalid("accepting br responses requires the `brotli` feature".to_string());
+        }
+        if cfg!(not(feature = "zstd"))
+            && (self.request_compression == RequestCompression::Zstd
+                || self.response_encodings().contains(&ResponseEncoding::Zstd))
+        {
+            return invalid("zstd compression requires the `zstd` feature".to_string());
+        }
+        if HeaderValue::try_from(self.user_agent()).is_err() {
+            return invalid("user_agent is not a valid header value".to_string());
+        }
+        let retries = self
+            .retry_count
+            .max(self.post_retry_count.unwrap_or_default());
+        if retries > 0 && self.retry_wait_time_sec == 0 {
+            return invalid(
+                "retry_wait_time_sec must be greater than zero when retries are enabled"
+                    .to_string(),
+            );
+        }
+
+        if self.connect_timeout_sec > self.connection_timeout_sec {
+            log::warn!(
+                "connect_timeout_sec ({}) exceeds connection_timeout_sec ({}) and has no effect",
+                self.connect_timeout_sec,
+                self.connection_timeout_sec
+            );
+        }
+        if let Some(max) = self.max_retry_interval_sec {
+            if max < self.retry_wait_time_sec {
+                log::warn!(
+                    "max_retry_interval_sec ({max}) is below retry_wait_time_sec ({}) and is ignored",
+                    self.retry_wait_time_sec
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn log_prefix(&self) -> String {
+        match &self.log_label {
+            Some(label) => format!("[Sindri client:{label}]"),
+            None => "[Sindri client]".to_string(),
+        }
+    }
+
+    fn user_agent(&self) -> String {
+        self.user_agent.clone().unwrap_or_else(|| {
+            format!(
+                "{}/{} (circuit {})",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION"),
+                self.circuit_version
+            )
+        })
+    }
+
+    fn proxy(&self) -> Result<Option<reqwest::Proxy>, CloudProverError> {
+        let Some(url) = &self.proxy_url else {
+            return Ok(None);
+        };
+        let proxy = reqwest::Proxy::all(url)
+            .map_err(|e| CloudProverError::InvalidConfig(format!("invalid proxy_url: {e}")))?;
+        Ok(Some(match &self.proxy_username {
+            Some(username) => {
+                proxy.basic_auth(username, self.proxy_password.as_deref().unwrap_or_default())
+            }
+            None => proxy,
+        }))
+    }
+
+    fn identity(&self) -> Result<Option<reqwest::Identity>, CloudProverError> {
+        let (cert_path, key_path) = match (&self.client_cert_path, &self.client_key_path) {
+            (None, None) => return Ok(None),
+            (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+            _ => {
+                return Err(CloudProverError::InvalidConfig(
+                    "client_cert_path and client_key_path must be set together".to_string(),
+                ))
+            }
+        };
+        let read = |path: &str| {
+            std::fs::read(path)
+                .map_err(|e| CloudProverError::InvalidConfig(format!("failed to read {path}: {e}")))
+        };
+        reqwest::Identity::from_pkcs8_pem(&read(cert_path)?, &read(key_path)?)
+            .map(Some)
+            .map_err(|e| {
+                CloudProverError::InvalidConfig(format!("invalid client certificate: {e}"))
+            })
+    }
+
+    // The accepted response encodings, most preferred first.
+    fn response_encodings(&self) -> Vec<ResponseEncoding> {
+        match &self.accept_encodings {
+            Some(encodings) => encodings.clone(),
+            None if self.response_zstd => vec![ResponseEncoding::Zstd],
+            None => Vec::new(),
+        }
+    }
+
+    // Headers sent with every request: `extra_headers`, and an `Accept-Encoding` weighting the
+    // accepted encodings by preference unless `extra_headers` sets one.
+    fn default_header_map(&self) -> Result<HeaderMap, CloudProverError> {
+        let mut headers = self.extra_header_map()?;
+        let encodings = self.response_encodings();
+        if !encodings.is_empty() && !headers.contains_key(ACCEPT_ENCODING) {
+            let value = encodings
+                .iter()
+                .enumerate()
+                .map(|(rank, encoding)| match rank {
+                    0 => encoding.token().to_string(),
+                    _ => format!("{};q=0.{}", encoding.token(), 10 - rank.min(9)),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            headers.insert(
+                ACCEPT_ENCODING,
+                HeaderValue::from_str(&value).expect("encoding tokens are valid header values"),
+            );
+        }
+        Ok(headers)
+    }
+
+    fn extra_header_map(&self) -> Result<HeaderMap, CloudProverError> {
+        self.extra_headers
+            .iter()
+            .map(|(name, value)| {
+                let invalid =
+                    || CloudProverError::InvalidConfig(format!("invalid header `{name}`"));
+                Ok((
+                    HeaderName::try_from(name.as_str()).map_err(|_| invalid())?,
+                    HeaderValue::try_from(value.as_str()).map_err(|_| invalid())?,
+                ))
+            })
+            .collect()
+    }
+}
+
+fn expand_env_vars_in(value: &mut serde_json::Value) -> Result<(), CloudProverError> {
+    match value {
+        serde_json::Value::String(s) => *s = expand_env_vars(s)?,
+        serde_json::Value::Array(values) => values.iter_mut().try_for_each(expand_env_vars_in)?,
+        serde_json::Value::Object(fields) => {
+            fields.values_mut().try_for_each(expand_env_vars_in)?
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// Generate a fluent setter for each listed config field.
+macro_rules! config_setters {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        $(
+            pub fn $field(self, $field: $ty) -> Self {
+                self.set(stringify!($field), $field)
+            }
+        )*
+    };
+}
+
+// Builds a `CloudProverConfig` in code.  `sdk_config` and `base_url` are required, as is `api_key`
+// unless `api_key_file` is given; every other field takes the same default as when it is omitted
+// from a config file, and the retry and timeout fields default to `DEFAULT_RETRY_COUNT`,
+// `DEFAULT_RETRY_WAIT_TIME_SEC` and `DEFAULT_CONNECTION_TIMEOUT_SEC`.
+#[derive(Default)]
+pub struct CloudProverConfigBuilder {
+    // The fields set so far, in the config's serialized form, so that `build` applies exactly the
+    // defaults of the file formats.
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl CloudProverConfigBuilder {
+    config_setters! {
+        sdk_config: SdkConfig,
+        base_url: &str,
+        api_path: &str,
+        api_key: &str,
+        api_key_file: &str,
+        retry_count: u32,
+        post_retry_count: u32,
+        retry_wait_time_sec: u64,
+        retry_jitter: RetryJitter,
+        max_retry_interval_sec: u64,
+        connection_timeout_sec: u64,
+        connect_timeout_sec: u64,
+        pool_max_idle_per_host: usize,
+        pool_idle_timeout_sec: u64,
+        max_in_flight_requests: usize,
+        circuit_breaker_threshold: u32,
+        circuit_breaker_cooldown_sec: u64,
+        per_method_timeout_sec: HashMap<String, u64>,
+        circuit_version: &str,
+        circuit_namespace: &str,
+        supported_circuit_versions: Vec<String>,
+        strict_version_check: bool,
+        vk_cache_ttl_sec: u64,
+        perform_verify: bool,
+        proof_input_field: &str,
+        extra_prove_fields: serde_json::Map<String, serde_json::Value>,
+        input_echo_capacity: usize,
+        extra_headers: HashMap<String, String>,
+        max_response_bytes: usize,
+        prove_dedup_window_sec: u64,
+        max_input_bytes: usize,
+        log_bodies: bool,
+        log_body_max_chars: Option<usize>,
+        log_label: &str,
+        request_compression: RequestCompression,
+        response_zstd: bool,
+        accept_encodings: Vec<ResponseEncoding>,
+        compression_min_body_size: usize,
+        dry_run: bool,
+        proxy_url: &str,
+        user_agent: &str,
+        proxy_username: &str,
+        proxy_password: &str,
+        client_cert_path: &str,
+        client_key_path: &str,
+    }
+
+    fn set(mut self, field: &str, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("config values serialize to JSON");
+        self.fields.insert(field.to_string(), value);
+        self
+    }
+
+    // Fill in defaults and validate the resulting config.
+    pub fn build(mut self) -> Result<CloudProverConfig, CloudProverError> {
+        for (field, default) in [
+            ("retry_count", DEFAULT_RETRY_COUNT.into()),
+            ("retry_wait_time_sec", DEFAULT_RETRY_WAIT_TIME_SEC.into()),
+            (
+                "connection_timeout_sec",
+                DEFAULT_CONNECTION_TIMEOUT_SEC.into(),
+            ),
+        ] {
+            self.fields.entry(field).or_insert(default);
+        }
+        let cfg = serde_json::from_value::<CloudProverConfig>(self.fields.into())
+            .map_err(|e| CloudProverError::InvalidConfig(e.to_string()))?
+            .load_api_key_file()?;
+        cfg.validate()?;
         Ok(cfg)
     }
 }
 
+// Cheap to clone; clones share the connection pool, the credentials, the vk cache and the
+// remembered inputs.
+#[derive(Clone)]
 pub struct CloudProver {
     base_url: Url,
-    api_key: String,
+    circuit_namespace: String,
+    // Shared with clones, so that `set_api_key` rotates the key for all of them.
+    credentials: Arc<RwLock<Arc<dyn CredentialProvider>>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    // Compression the client built by `try_new` applies to request bodies, and the smallest body it
+    // applies to.  Unknown with `with_client`, whose client takes care of compression.
+    request_compression: Option<(RequestCompression, usize)>,
+    circuit_versions: Vec<String>,
+    strict_version_check: bool,
     send_timeout: Duration,
+    method_timeouts: HashMap<String, Duration>,
     client: ClientWithMiddleware,
+    metrics: Arc<dyn MetricsRecorder>,
+    // Retries of requests sent so far, counted across clones.
+    retries: Arc<AtomicU64>,
+    in_flight: Option<Arc<Semaphore>>,
+    breaker: Option<Arc<Mutex<CircuitBreaker>>>,
+    // From the latest response carrying rate-limit headers, across clones.
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
+    // Each request holds one permit; `shutdown` takes them all and then closes it.
+    active_requests: Arc<Semaphore>,
+    vk_cache_ttl: Option<Duration>,
+    vk_cache: Arc<Mutex<VkCache>>,
+    // Circuit whose detail endpoint is used as a lightweight authenticated probe.
+    ping_circuit_type: CircuitType,
+    perform_verify: bool,
+    proof_input_field: String,
+    extra_prove_fields: serde_json::Map<String, serde_json::Value>,
+    task_inputs: Arc<Mutex<TaskInputs>>,
+    task_states: Arc<Mutex<TaskStates>>,
+    max_response_bytes: Option<usize>,
+    prove_dedup_window: Option<Duration>,
+    recent_submissions: Arc<Mutex<RecentSubmissions>>,
+    max_input_bytes: Option<usize>,
+    log_bodies: bool,
+    log_body_max_chars: Option<usize>,
+    log_prefix: String,
+    dry_run: bool,
 }
 
-#[derive(Deserialize)]
-pub struct VerificationKey {
-    verification_key: String,
+// Reads a response body for the JSON parser, enforcing the size limit and optionally keeping a copy
+// of the start of the body for logging.  Failures of the underlying reader are recorded so that
+// they can be told apart from malformed JSON once parsing stops.
+struct BodyReader<R> {
+    inner: R,
+    limit: Option<usize>,
+    read: usize,
+    captured: Option<Vec<u8>>,
+    capture_limit: usize,
+    exceeded_limit: Option<usize>,
+    read_error: Option<std::io::Error>,
 }
 
-#[derive(Deserialize)]
-pub struct SindriProofInfoResponse {
-    pub compute_time_sec: Option<f64>,
-    pub date_created: String,
-    pub error: Option<String>,
-    pub proof_id: String,
-    pub proof: Option<serde_json::Value>,
-    pub queue_time_sec: Option<f64>,
-    pub status: SindriTaskStatus,
-    pub verification_key: Option<VerificationKey>,
+impl<R> BodyReader<R> {
+    // Keeps up to `capture` bytes if set.
+    fn new(inner: R, limit: Option<usize>, capture: Option<usize>) -> Self {
+        Self {
+            inner,
+            limit,
+            read: 0,
+            captured: capture.map(|_| Vec::new()),
+            capture_limit: capture.unwrap_or_default(),
+            exceeded_limit: None,
+            read_error: None,
+        }
+    }
 }
 
-#[derive(Deserialize)]
-pub enum SindriTaskStatus {
-    #[serde(rename = "Queued")]
-    Queued,
-    #[serde(rename = "In Progress")]
-    Proving,
-    #[serde(rename = "Ready")]
-    Success,
-    #[serde(rename = "Failed")]
-    Failed,
+impl<R: std::io::Read> std::io::Read for BodyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = match self.inner.read(buf) {
+            Ok(n) => n,
+            Err(e) => {
+                let kind = e.kind();
+                self.read_error = Some(e);
+                return Err(kind.into());
+            }
+        };
+        self.read += n;
+        if let Some(limit) = self.limit.filter(|limit| self.read > *limit) {
+            self.exceeded_limit = Some(limit);
+            return Err(std::io::ErrorKind::InvalidData.into());
+        }
+        if let Some(captured) = &mut self.captured {
+            let take = n.min(self.capture_limit.saturating_sub(captured.len()));
+            captured.extend_from_slice(&buf[..take]);
+        }
+        Ok(n)
+    }
 }
 
-impl From<SindriTaskStatus> for TaskStatus {
-    fn from(status: SindriTaskStatus) -> Self {
-        match status {
-            SindriTaskStatus::Queued => TaskStatus::Queued,
-            SindriTaskStatus::Proving => TaskStatus::Proving,
-            SindriTaskStatus::Success => TaskStatus::Success,
-            SindriTaskStatus::Failed => TaskStatus::Failed,
+// A response body decompressed according to its `Content-Encoding`, counting the bytes received
+// before decompression.  Encodings this build cannot decode are passed through as is, and then fail
+// to parse.
+struct DecodedBody {
+    reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
+    wire_bytes: Arc<AtomicUsize>,
+    compressed: bool,
+}
+
+impl DecodedBody {
+    fn new(response: reqwest::Response) -> Self {
+        use async_compression::tokio::bufread;
+
+        let encoding = response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_ascii_lowercase());
+        let wire_bytes = Arc::new(AtomicUsize::new(0));
+        let counter = wire_bytes.clone();
+        let stream = Box::pin(response.bytes_stream().map(move |chunk| {
+            let chunk = chunk.map_err(std::io::Error::other)?;
+            counter.fetch_add(chunk.len(), Ordering::Relaxed);
+            Ok::<_, std::io::Error>(chunk)
+        }));
+        let raw = StreamReader::new(stream);
+        let (reader, compressed): (Box<dyn tokio::io::AsyncRead + Send + Unpin>, _) =
+            match encoding.as_deref() {
+                Some("gzip") => (Box::new(bufread::GzipDecoder::new(raw)), true),
+                Some("deflate") => (Box::new(bufread::ZlibDecoder::new(raw)), true),
+                #[cfg(feature = "zstd")]
+                Some("zstd") => (Box::new(bufread::ZstdDecoder::new(raw)), true),
+                #[cfg(feature = "brotli")]
+                Some("br") => (Box::new(bufread::BrotliDecoder::new(raw)), true),
+                _ => (Box::new(raw), false),
+            };
+        Self {
+            reader,
+            wire_bytes,
+            compressed,
         }
     }
+
+    fn wire_bytes(&self) -> usize {
+        self.wire_bytes.load(Ordering::Relaxed)
+    }
 }
 
-enum MethodClass {
-    Circuit(CircuitType),
-    Proof(String),
+// Surface failures to receive the body as transport errors, and failures to decompress it, e.g. at
+// a cut-off zstd frame, as an incomplete body.
+fn body_read_error(e: std::io::Error) -> CloudProverError {
+    if e.get_ref()
+        .is_some_and(|inner| inner.is::<reqwest::Error>())
+    {
+        let inner = e
+            .into_inner()
+            .unwrap()
+            .downcast::<reqwest::Error>()
+            .unwrap();
+        return (*inner).into();
+    }
+    CloudProverError::IncompleteBody(e)
 }
 
-// Re-encode the vk because the encoding scheme used by Sindri is different from the one used in scroll internally.
-fn reformat_vk(vk_old: String) -> anyhow::Result<String> {
-    log::debug!("vk_old: {:?}", vk_old);
+// `body` compressed as a whole, for a request whose body must be known as sent, such as a signed
+// one, rather than streamed through the compression middleware.
+async fn compress_body(compression: RequestCompression, body: &[u8]) -> std::io::Result<Vec<u8>> {
+    use async_compression::tokio::write;
 
-    // decode base64 without padding
-    let vk = base64::decode_config(vk_old, base64::URL_SAFE_NO_PAD)?;
-    // encode with padding
-    let vk_new = base64::encode_config(vk, base64::STANDARD);
+    let mut compressed = Vec::new();
+    match compression {
+        #[cfg(feature = "zstd")]
+        RequestCompression::Zstd => {
+            let mut encoder = write::ZstdEncoder::new(&mut compressed);
+            encoder.write_all(body).await?;
+            encoder.shutdown().await?;
+        }
+        #[cfg(not(feature = "zstd"))]
+        RequestCompression::Zstd => unreachable!("rejected by `validate` without zstd"),
+        RequestCompression::Gzip => {
+            let mut encoder = write::GzipEncoder::new(&mut compressed);
+            encoder.write_all(body).await?;
+            encoder.shutdown().await?;
+        }
+        RequestCompression::None => compressed.extend_from_slice(body),
+    }
+    Ok(compressed)
+}
 
-    Ok(vk_new)
+// Reformatted verification keys and their fetch time, keyed by (circuit version, circuit slug).
+type VkCache = HashMap<(String, &'static str), (String, Instant)>;
+
+// Identifies a proof submission by (input sha256, circuit type, circuit version), the input being
+// the one sent to Sindri.
+type SubmissionKey = (String, String, String);
+
+// Recent submissions, for `prove_dedup_window_sec`.
+type RecentSubmissions = HashMap<SubmissionKey, RecentSubmission>;
+
+enum RecentSubmission {
+    // Submitted as the task, at the time.
+    Submitted(String, Instant),
+    // Being submitted.  The task id is sent once Sindri accepts the submission; the sender is
+    // dropped if it fails.
+    Pending(tokio::sync::watch::Receiver<Option<String>>),
 }
 
-#[async_trait]
-impl ProvingService for CloudProver {
-    fn is_local(&self) -> bool {
-        false
+// Outcome of looking up a submission in the dedup window.
+enum Submission {
+    // The same input was submitted as this task.
+    Earlier(String),
+    // The submission is to be sent, with identical ones waiting for it meanwhile.
+    Claimed(PendingSubmission),
+}
+
+// A submission reserved in `RecentSubmissions`.  Dropping it without `complete`, e.g. when the
+// submission failed or was cancelled, frees the reservation, and one of the waiting identical
+// submissions is sent instead.
+struct PendingSubmission {
+    recent: Arc<Mutex<RecentSubmissions>>,
+    key: SubmissionKey,
+    sender: tokio::sync::watch::Sender<Option<String>>,
+}
+
+impl PendingSubmission {
+    fn complete(self, task_id: &str) {
+        self.recent.lock().unwrap().insert(
+            self.key.clone(),
+            RecentSubmission::Submitted(task_id.to_string(), Instant::now()),
+        );
+        self.sender.send_replace(Some(task_id.to_string()));
     }
+}
 
-    // There are three steps to prove a circuit:
-    // 1. Get the verification key from the Sindri proving service.
-    // 2. Submit a proof to the Sindri proving service.
-    // 3. Query the status of the proof task.
+impl Drop for PendingSubmission {
+    fn drop(&mut self) {
+        let mut recent = self.recent.lock().unwrap();
+        if matches!(recent.get(&self.key), Some(RecentSubmission::Pending(_))) {
+            recent.remove(&self.key);
+        }
+    }
+}
 
-    async fn get_vks(&self, req: GetVkRequest) -> GetVkResponse {
-        if req.circuit_version != THIS_CIRCUIT_VERSION {
-            return GetVkResponse {
-                vks: Vec::new(),
-                error: Some("circuit version mismatch".to_string()),
-            };
-        };
+// Hex-encoded SHA-256 of a proof input as submitted to Sindri, i.e. the output of
+// `reprocess_prove_input`, for linking a task id to its input in audit logs.
+pub fn input_sha256(input: &str) -> String {
+    hex::encode(Sha256::digest(input.as_bytes()))
+}
 
-        #[derive(serde::Deserialize)]
-        struct SindriCircuitInfoResponse {
-            verification_key: VerificationKey,
+// Inputs of submitted tasks by task id, evicting the oldest beyond `capacity`.
+struct TaskInputs {
+    capacity: usize,
+    order: VecDeque<String>,
+    inputs: HashMap<String, String>,
+}
+
+impl TaskInputs {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::new(),
+            inputs: HashMap::new(),
         }
+    }
 
-        let mut vks: Vec<String> = Vec::new();
-        for circuit_type in req.circuit_types {
-            match self
-                .get_with_token::<SindriCircuitInfoResponse>(
-                    MethodClass::Circuit(circuit_type),
-                    "detail",
-                    None,
-                )
-                .await
-            {
-                Ok(resp) => match reformat_vk(resp.verification_key.verification_key) {
-                    Ok(vk) => {
-                        if !vks.contains(&vk) {
-                            vks.push(vk)
-                        }
-                    }
-                    Err(e) => {
-                        return GetVkResponse {
-                            vks,
-                            error: Some(e.to_string()),
-                        }
-                    }
-                },
-                Err(e) => {
-                    return GetVkResponse {
-                        vks,
-                        error: Some(e.to_string()),
-                    }
-                }
+    // Takes the input by reference so that it is only copied when echoing is enabled; inputs can
+    // run to megabytes.
+    fn insert(&mut self, task_id: &str, input: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self
+            .inputs
+            .insert(task_id.to_string(), input.to_string())
+            .is_none()
+        {
+            self.order.push_back(task_id.to_string());
+        }
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.inputs.remove(&evicted);
             }
         }
+    }
 
-        GetVkResponse { vks, error: None }
+    fn get(&self, task_id: &str) -> Option<String> {
+        self.inputs.get(task_id).cloned()
     }
+}
 
-    async fn prove(&self, req: ProveRequest) -> ProveResponse {
-        if req.circuit_version != THIS_CIRCUIT_VERSION {
-            return build_prove_error_response(&req, "circuit version mismatch");
-        };
+// Last status seen for each task and when it was first seen, so that status changes can be
+// reported.  Tasks are forgotten once finished, and the longest unchanged beyond `capacity`.
+struct TaskStates {
+    capacity: usize,
+    states: HashMap<String, (SindriTaskStatus, Instant)>,
+}
 
-        let input = match reprocess_prove_input(&req) {
-            Ok(input) => input,
+impl TaskStates {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            states: HashMap::new(),
+        }
+    }
+
+    // Record `status` for the task, returning the previous status and how long it lasted if the
+    // status changed.
+    fn observe(
+        &mut self,
+        task_id: &str,
+        status: &SindriTaskStatus,
+    ) -> Option<(SindriTaskStatus, Duration)> {
+        let now = Instant::now();
+        let previous = match self.states.get(task_id) {
+            Some((seen, _)) if seen == status => return None,
+            Some((seen, since)) => Some((seen.clone(), now - *since)),
+            None => None,
+        };
+        if !matches!(status, SindriTaskStatus::Queued | SindriTaskStatus::Proving) {
+            self.states.remove(task_id);
+            return previous;
+        }
+        self.states
+            .insert(task_id.to_string(), (status.clone(), now));
+        if self.states.len() > self.capacity {
+            let stalest = self
+                .states
+                .iter()
+                .min_by_key(|(_, (_, since))| *since)
+                .map(|(task_id, _)| task_id.clone());
+            if let Some(stalest) = stalest {
+                self.states.remove(&stalest);
+            }
+        }
+        previous
+    }
+}
+
+// Counts consecutive failed requests, refusing requests for `cooldown` once `threshold` is reached.
+struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    failures: u32,
+    open_until: Option<Instant>,
+    log_prefix: String,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration, log_prefix: String) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            failures: 0,
+            open_until: None,
+            log_prefix,
+        }
+    }
+
+    // Whether a request may be sent.  Once the cooldown has passed, one trial request is let
+    // through and the breaker stays open for another cooldown meanwhile, so that a trial that never
+    // reports back does not keep the breaker open forever.
+    fn check(&mut self) -> Result<(), CloudProverError> {
+        let Some(open_until) = self.open_until else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if now < open_until {
+            return Err(CloudProverError::CircuitOpen {
+                retry_in: open_until - now,
+            });
+        }
+        self.open_until = Some(now + self.cooldown);
+        Ok(())
+    }
+
+    fn record(&mut self, success: bool) {
+        if success {
+            self.failures = 0;
+            self.open_until = None;
+            return;
+        }
+        self.failures = self.failures.saturating_add(1);
+        if self.failures >= self.threshold {
+            if self.open_until.is_none() {
+                log::warn!(
+                    "{}, {} consecutive requests failed, pausing requests for {:?}",
+                    self.log_prefix,
+                    self.failures,
+                    self.cooldown
+                );
+            }
+            self.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VerificationKey {
+    verification_key: String,
+}
+
+// Metadata of a circuit hosted by Sindri, as returned by its detail endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CircuitDetail {
+    pub circuit_id: String,
+    pub circuit_name: String,
+    // Proving framework of the circuit, e.g. `halo2`.
+    #[serde(default)]
+    pub circuit_type: Option<String>,
+    #[serde(default)]
+    pub proving_scheme: Option<String>,
+    // Circuits are compiled before they can prove, and go through the same states as proofs.
+    pub status: SindriTaskStatus,
+    #[serde(deserialize_with = "string_or_number")]
+    pub date_created: String,
+    #[serde(default)]
+    pub num_proofs: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub verification_key: Option<VerificationKey>,
+}
+
+impl CircuitDetail {
+    pub fn created_at(&self) -> Result<DateTime<Utc>, CloudProverError> {
+        parse_timestamp(&self.date_created)
+    }
+
+    // The verification key in Scroll's encoding, once the circuit has been compiled.
+    pub fn vk(&self) -> Result<Option<String>, CloudProverError> {
+        self.verification_key
+            .as_ref()
+            .map(|vk| reformat_vk(vk.verification_key.clone()))
+            .transpose()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SindriProofInfoResponse {
+    // Identifier of the circuit that produced the proof, e.g. `scroll-tech/batch_prover:v0.13.1`.
+    #[serde(default)]
+    pub circuit_name: Option<String>,
+    // Client-supplied metadata attached at submission, e.g. by `prove_with_reference`.
+    #[serde(default)]
+    pub meta: Option<HashMap<String, String>>,
+    pub compute_time_sec: Option<f64>,
+    // RFC 3339, or seconds since the Unix epoch; see `created_at` for the parsed value.
+    #[serde(deserialize_with = "string_or_number")]
+    pub date_created: String,
+    pub error: Option<String>,
+    // Sindri's identifier of the proof, which currently doubles as the SDK's task id; see
+    // `task_id`.
+    pub proof_id: String,
+    // Kept as the server's exact bytes, so that re-serializing it for `ProveResponse` and
+    // `QueryTaskResponse` preserves key order and number formatting for anyone hashing the proof.
+    pub proof: Option<Box<serde_json::value::RawValue>>,
+    // Public inputs of the proof, returned when requested with `include_public`.
+    #[serde(default)]
+    pub public: Option<serde_json::Value>,
+    pub queue_time_sec: Option<f64>,
+    pub status: SindriTaskStatus,
+    pub verification_key: Option<VerificationKey>,
+}
+
+impl SindriProofInfoResponse {
+    // The SDK task id of this proof.  Sindri has no separate submission id, so the proof id is
+    // used; every response translated for the SDK goes through here so that the mapping can
+    // change in one place should the API ever tell the two apart.
+    pub fn task_id(&self) -> &str {
+        &self.proof_id
+    }
+
+    // When the proof was submitted.
+    pub fn created_at(&self) -> Result<DateTime<Utc>, CloudProverError> {
+        parse_timestamp(&self.date_created)
+    }
+
+    // Sindri may briefly report a proof as ready before the proof itself is available.  When a
+    // proof is expected, such a task is reported as still proving so that it is polled again.
+    pub fn detailed_status(&self, expect_proof: bool) -> DetailedTaskStatus {
+        let detail = Some(self.status_detail(expect_proof));
+        if self.awaiting_proof(expect_proof) {
+            return DetailedTaskStatus {
+                status: TaskStatus::Proving,
+                detail,
+            };
+        }
+        DetailedTaskStatus {
+            status: self.status.clone().into(),
+            detail,
+        }
+    }
+
+    fn awaiting_proof(&self, expect_proof: bool) -> bool {
+        expect_proof && self.status == SindriTaskStatus::Success && self.proof.is_none()
+    }
+
+    // Sindri's status, followed by the reason for it where there is one.
+    fn status_detail(&self, expect_proof: bool) -> String {
+        let reason = match self.status {
+            _ if self.awaiting_proof(expect_proof) => Some("proof not yet available"),
+            SindriTaskStatus::Failed | SindriTaskStatus::Unknown(_) => {
+                self.error.as_deref().filter(|error| !error.is_empty())
+            }
+            _ => None,
+        };
+        let status = String::from(self.status.clone());
+        match reason {
+            Some(reason) => format!("{status}: {reason}"),
+            None => status,
+        }
+    }
+}
+
+// Accept a JSON number where a string is expected, keeping its textual form.
+fn string_or_number<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(s),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "expected a string or number, got {other}"
+        ))),
+    }
+}
+
+// Statuses this SDK does not know about yet are kept as `Unknown` rather than failing the whole
+// response, so that a new status on Sindri's side degrades gracefully.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum SindriTaskStatus {
+    Queued,
+    Proving,
+    Success,
+    Failed,
+    Unknown(String),
+}
+
+impl From<String> for SindriTaskStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "Queued" => SindriTaskStatus::Queued,
+            "In Progress" => SindriTaskStatus::Proving,
+            "Ready" => SindriTaskStatus::Success,
+            "Failed" => SindriTaskStatus::Failed,
+            _ => SindriTaskStatus::Unknown(status),
+        }
+    }
+}
+
+impl From<SindriTaskStatus> for String {
+    fn from(status: SindriTaskStatus) -> Self {
+        match status {
+            SindriTaskStatus::Queued => "Queued".to_string(),
+            SindriTaskStatus::Proving => "In Progress".to_string(),
+            SindriTaskStatus::Success => "Ready".to_string(),
+            SindriTaskStatus::Failed => "Failed".to_string(),
+            SindriTaskStatus::Unknown(status) => status,
+        }
+    }
+}
+
+impl From<SindriTaskStatus> for TaskStatus {
+    fn from(status: SindriTaskStatus) -> Self {
+        match status {
+            SindriTaskStatus::Queued => TaskStatus::Queued,
+            SindriTaskStatus::Proving => TaskStatus::Proving,
+            SindriTaskStatus::Success => TaskStatus::Success,
+            SindriTaskStatus::Failed => TaskStatus::Failed,
+            // New statuses are most likely terminal ones such as a cancellation or a timeout, and
+            // reporting them as failed lets the coordinator reassign the task.
+            SindriTaskStatus::Unknown(_) => TaskStatus::Failed,
+        }
+    }
+}
+
+// A converted status along with what the conversion loses: the Sindri status it came from, and
+// the reason for it where there is one, e.g. "Failed: out of memory".
+pub struct DetailedTaskStatus {
+    pub status: TaskStatus,
+    pub detail: Option<String>,
+}
+
+impl From<SindriTaskStatus> for DetailedTaskStatus {
+    fn from(status: SindriTaskStatus) -> Self {
+        Self {
+            detail: Some(String::from(status.clone())),
+            status: status.into(),
+        }
+    }
+}
+
+// Sindri-specific task details that have no place in the SDK's response structs.
+#[derive(Debug, Clone, Default)]
+pub struct TaskMetadata {
+    // Sindri's own identifier of the proof, as opposed to the SDK task id derived from it.
+    pub proof_id: Option<String>,
+    // The task's status as Sindri reported it, before conversion to a `TaskStatus`.
+    pub sindri_status: Option<SindriTaskStatus>,
+    pub queue_time_sec: Option<f64>,
+    // The compute time as Sindri reported it.  Unlike the `compute_time_sec` of the SDK responses,
+    // which is only set once the task has finished, it is also kept while the task is proving,
+    // when it is the compute time so far.
+    pub compute_time_sec: Option<f64>,
+    // Sindri's own status of the task, see `DetailedTaskStatus`.
+    pub status_detail: Option<String>,
+    pub public: Option<serde_json::Value>,
+    // Set when the task failed, classifying why.
+    pub failure: Option<ProofFailure>,
+    // SHA-256 of the submitted input, see `input_sha256`.  Only set by the prove calls that
+    // reprocess the input.
+    pub input_sha256: Option<String>,
+    // Client-observed time from sending the request that produced this metadata until its response
+    // was read, including any retries.  Compared with Sindri's own queue and compute times, it
+    // tells network problems apart from slow proving.
+    pub round_trip: Option<Duration>,
+}
+
+impl TaskMetadata {
+    pub fn from_response(resp: &SindriProofInfoResponse) -> Self {
+        let failure = match resp.status {
+            SindriTaskStatus::Failed | SindriTaskStatus::Unknown(_) => Some(
+                ProofFailure::from_error(resp.error.as_deref().unwrap_or_default()),
+            ),
+            _ => None,
+        };
+        Self {
+            proof_id: Some(resp.proof_id.clone()),
+            sindri_status: Some(resp.status.clone()),
+            queue_time_sec: resp.queue_time_sec,
+            compute_time_sec: resp.compute_time_sec,
+            status_detail: Some(resp.status_detail(false)),
+            public: resp.public.clone(),
+            failure,
+            input_sha256: None,
+            round_trip: None,
+        }
+    }
+}
+
+// Why Sindri failed a proof, so callers can tell bad input (resubmitting will not help) from a
+// prover-side crash or resource problem (resubmitting may).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureCategory {
+    Input,
+    Transient,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofFailure {
+    pub category: FailureCategory,
+    pub message: String,
+}
+
+impl ProofFailure {
+    // Classify the `error` of a failed proof.  Sindri may report either a structured JSON payload
+    // (`category` or `code`, plus `message` or `detail`) or a plain message; in both cases the
+    // category is inferred from well-known keywords when it is not given explicitly.
+    pub fn from_error(error: &str) -> Self {
+        #[derive(Deserialize)]
+        struct SindriErrorPayload {
+            #[serde(alias = "code")]
+            category: Option<String>,
+            #[serde(alias = "detail")]
+            message: Option<String>,
+        }
+
+        let (category, message) = match serde_json::from_str::<SindriErrorPayload>(error) {
+            Ok(payload) => {
+                let message = payload.message.unwrap_or_else(|| error.to_string());
+                (payload.category.unwrap_or_else(|| message.clone()), message)
+            }
+            Err(_) => (error.to_string(), error.to_string()),
+        };
+
+        Self {
+            category: FailureCategory::classify(&category),
+            message,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.category == FailureCategory::Transient
+    }
+}
+
+impl FailureCategory {
+    fn classify(text: &str) -> Self {
+        let text = text.to_ascii_lowercase();
+        let matches_any = |keywords: &[&str]| keywords.iter().any(|k| text.contains(k));
+        if matches_any(&["input", "invalid", "validation", "malformed", "deserializ"]) {
+            FailureCategory::Input
+        } else if matches_any(&[
+            "timeout",
+            "timed out",
+            "internal",
+            "memory",
+            "resource",
+            "unavailable",
+            "crash",
+            "killed",
+        ]) {
+            FailureCategory::Transient
+        } else {
+            FailureCategory::Unknown
+        }
+    }
+}
+
+enum MethodClass {
+    Circuit(CircuitType, String),
+    Proof(String),
+    Proofs,
+}
+
+// The verification key of one circuit type, as returned by `CloudProver::vks_by_circuit`.
+#[derive(Debug)]
+pub struct CircuitVk {
+    pub circuit_type: CircuitType,
+    pub vk: Result<String, CloudProverError>,
+}
+
+// Rate-limit state reported by Sindri in the `X-RateLimit-*` headers of a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    // `X-RateLimit-Reset` is read as seconds from the response, or as a Unix timestamp when too
+    // large to be a delay.
+    pub reset_at: Option<SystemTime>,
+}
+
+// `X-RateLimit-Reset` values above this are Unix timestamps rather than delays.
+const RATE_LIMIT_RESET_EPOCH_THRESHOLD: u64 = 1_000_000_000;
+
+impl RateLimit {
+    // The rate limit reported by the headers, or `None` when none of them is present.  Unparsable
+    // values are ignored.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let get = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        let reset_at = get("x-ratelimit-reset").map(|reset| {
+            if reset > RATE_LIMIT_RESET_EPOCH_THRESHOLD {
+                SystemTime::UNIX_EPOCH + Duration::from_secs(reset)
+            } else {
+                SystemTime::now() + Duration::from_secs(reset)
+            }
+        });
+        let rate_limit = Self {
+            limit: get("x-ratelimit-limit"),
+            remaining: get("x-ratelimit-remaining"),
+            reset_at,
+        };
+        (rate_limit.limit.is_some() || rate_limit.remaining.is_some() || reset_at.is_some())
+            .then_some(rate_limit)
+    }
+}
+
+// Filters for `CloudProver::list_proofs`.  `limit` caps the total number of proofs returned across
+// all pages; `None` follows pagination until the listing is exhausted.
+#[derive(Debug, Clone, Default)]
+pub struct ListProofsParams {
+    pub status: Option<SindriTaskStatus>,
+    pub circuit_type: Option<CircuitType>,
+    // Defaults to the primary configured circuit version when filtering by circuit type.
+    pub circuit_version: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+    // Only proofs submitted with this reference through `CloudProver::prove_with_reference`.
+    pub reference: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SindriProofListResponse {
+    count: usize,
+    items: Vec<SindriProofInfoResponse>,
+}
+
+// Header carrying the idempotency key of a prove submission.
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+// Key of the proof metadata entry holding the reference given to `prove_with_reference`.
+const REFERENCE_META_KEY: &str = "reference";
+
+// Number of proofs requested per page when listing proofs.
+const LIST_PROOFS_PAGE_SIZE: usize = 100;
+
+// Permits of `CloudProver::active_requests`, more than there are ever concurrent requests.
+const SHUTDOWN_PERMITS: u32 = 1 << 28;
+
+// Number of unfinished tasks whose status is tracked for reporting status changes.
+const TRACKED_TASKS_CAPACITY: usize = 10_000;
+
+// Number of proofs `query_tasks` queries at the same time.
+const QUERY_TASKS_CONCURRENCY: usize = 8;
+
+// Number of recent successful proofs averaged by `CloudProver::estimate`.
+const ESTIMATE_SAMPLE_SIZE: usize = 20;
+
+// Expected timing of a proof, as returned by `CloudProver::estimate`.  The averages are `None`
+// when there are no successful proofs to average.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EstimateInfo {
+    // Number of recent successful proofs the averages are based on.
+    pub sample_size: usize,
+    pub avg_compute_time_sec: Option<f64>,
+    pub avg_queue_time_sec: Option<f64>,
+    // Proofs of this circuit submitted by the account that have not started yet.
+    pub queued: usize,
+}
+
+// Return the Sindri identifier of the circuit serving this circuit type and version.
+pub(crate) fn circuit_id(
+    circuit_namespace: &str,
+    circuit_type: CircuitType,
+    circuit_version: &str,
+) -> Result<String, CloudProverError> {
+    Ok(format!(
+        "{}/{}:{}",
+        circuit_namespace,
+        circuit_slug(circuit_type)?,
+        circuit_version
+    ))
+}
+
+// Return the name under which Sindri hosts the circuit for this circuit type.
+pub fn circuit_slug(circuit_type: CircuitType) -> Result<&'static str, CloudProverError> {
+    match circuit_type {
+        CircuitType::Chunk => Ok("chunk_prover"),
+        CircuitType::Batch => Ok("batch_prover"),
+        CircuitType::Bundle => Ok("bundle_prover"),
+        CircuitType::Undefined => Err(CloudProverError::UndefinedCircuitType),
+    }
+}
+
+// Inverse of `circuit_id`: recover the circuit type and version from a Sindri circuit identifier
+// such as `scroll-tech/batch_prover:v0.13.1`.  The namespace is optional.
+pub fn parse_circuit_slug(s: &str) -> Result<(CircuitType, String), CloudProverError> {
+    let invalid = || CloudProverError::InvalidCircuitSlug(s.to_string());
+    let name = s.rsplit_once('/').map_or(s, |(_, name)| name);
+    let (slug, version) = name.split_once(':').ok_or_else(invalid)?;
+    if version.is_empty() {
+        return Err(invalid());
+    }
+    let circuit_type = match slug {
+        "chunk_prover" => CircuitType::Chunk,
+        "batch_prover" => CircuitType::Batch,
+        "bundle_prover" => CircuitType::Bundle,
+        _ => return Err(invalid()),
+    };
+    Ok((circuit_type, version.to_string()))
+}
+
+// Re-encode the vk because the encoding scheme used by Sindri is different from the one used in scroll internally.
+pub fn reformat_vk(vk_old: String) -> Result<String, CloudProverError> {
+    log::debug!("vk_old: {:?}", vk_old);
+
+    // decode base64 without padding
+    let vk = base64::decode_config(vk_old, base64::URL_SAFE_NO_PAD)?;
+    // encode with padding
+    let vk_new = base64::encode_config(vk, base64::STANDARD);
+
+    Ok(vk_new)
+}
+
+// Inverse of `reformat_vk`: re-encode a vk from Scroll's encoding into the one used by Sindri.
+pub fn reformat_vk_to_sindri(vk: String) -> Result<String, CloudProverError> {
+    // decode base64 with padding
+    let vk = base64::decode_config(vk, base64::STANDARD)?;
+    // encode without padding
+    Ok(base64::encode_config(vk, base64::URL_SAFE_NO_PAD))
+}
+
+#[async_trait]
+impl ProvingService for CloudProver {
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    // There are three steps to prove a circuit:
+    // 1. Get the verification key from the Sindri proving service.
+    // 2. Submit a proof to the Sindri proving service.
+    // 3. Query the status of the proof task.
+
+    async fn get_vks(&self, req: GetVkRequest) -> GetVkResponse {
+        let mut resp = traced!(
+            tracing::info_span!(
+                "sindri.get_vks",
+                method = "get_vks",
+                circuit_types = ?req.circuit_types,
+                circuit_version = %req.circuit_version,
+            ),
+            self.fetch_vks(req)
+        )
+        .await;
+        resp.error = self.label_error(resp.error);
+        resp
+    }
+
+    async fn prove(&self, req: ProveRequest) -> ProveResponse {
+        let mut resp = traced!(
+            tracing::info_span!(
+                "sindri.prove",
+                method = "prove",
+                circuit_type = ?req.circuit_type,
+                circuit_version = %req.circuit_version,
+                task_id = tracing::field::Empty,
+            ),
+            self.prove_with_metadata(req)
+        )
+        .await
+        .0;
+        resp.error = self.label_error(resp.error);
+        resp
+    }
+
+    async fn query_task(&self, req: QueryTaskRequest) -> QueryTaskResponse {
+        let mut resp = traced!(
+            tracing::info_span!("sindri.query_task", method = "query_task", task_id = %req.task_id),
+            self.query_task_with_metadata(req)
+        )
+        .await
+        .0;
+        resp.error = self.label_error(resp.error);
+        resp
+    }
+}
+
+// Optional fields to include when querying a proof.  The default fetches only the status and
+// timing, which is cheap enough to poll; request the heavy fields once the task has succeeded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryOptions {
+    pub include_proof: bool,
+    pub include_public: bool,
+    pub include_vk: bool,
+}
+
+impl QueryOptions {
+    // Include every optional field.
+    pub fn full() -> Self {
+        Self {
+            include_proof: true,
+            include_public: true,
+            include_vk: true,
+        }
+    }
+
+    fn query_params(&self) -> HashMap<String, String> {
+        [
+            ("include_proof", self.include_proof),
+            ("include_public", self.include_public),
+            ("include_verification_key", self.include_vk),
+        ]
+        .iter()
+        .map(|&(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+}
+
+// Sent unchanged with every retry of a submission, so that Sindri can recognize an attempt it
+// already accepted even though the response never arrived.
+fn idempotency_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        IDEMPOTENCY_KEY,
+        HeaderValue::from_str(&hex::encode(rand::random::<[u8; 16]>()))
+            .expect("hex is a valid header value"),
+    );
+    headers
+}
+
+// Warn about a status that `detailed_status` reports as another one: a proof that is ready without
+// a proof yet, or a status this client does not know.
+fn warn_unexpected_status(log_prefix: &str, resp: &SindriProofInfoResponse, expect_proof: bool) {
+    if resp.awaiting_proof(expect_proof) {
+        log::warn!(
+            "{log_prefix}, proof {} is ready but has no proof yet",
+            resp.proof_id
+        );
+    }
+    if let SindriTaskStatus::Unknown(status) = &resp.status {
+        log::warn!("{log_prefix}, unknown task status {status:?}, treating as failed");
+    }
+}
+
+// Translate Sindri's answer to a prove request, echoing the input if `echo_input` is set.  A 202
+// Accepted means that the proof was queued rather than found complete, whatever the possibly
+// stale status in the body says.
+fn prove_response(
+    log_prefix: &str,
+    resp: SindriProofInfoResponse,
+    http_status: StatusCode,
+    elapsed: Duration,
+    req: ProveRequest,
+    echo_input: bool,
+) -> (ProveResponse, TaskMetadata) {
+    let stats = proving_timestamps_from_response(log_prefix, &resp);
+    let mut metadata = TaskMetadata::from_response(&resp);
+    metadata.round_trip = Some(elapsed);
+    warn_unexpected_status(log_prefix, &resp, true);
+    let DetailedTaskStatus { mut status, detail } = resp.detailed_status(true);
+    if http_status == StatusCode::ACCEPTED && matches!(status, TaskStatus::Success) {
+        log::debug!(
+            "{log_prefix}, proof {} was accepted, reporting it as queued",
+            resp.proof_id
+        );
+        status = TaskStatus::Queued;
+    }
+    metadata.status_detail = detail;
+    let stats = finished_stats(stats, &status);
+    record_task_id(resp.task_id());
+    let response = ProveResponse {
+        task_id: resp.task_id().to_string(),
+        circuit_type: req.circuit_type,
+        circuit_version: req.circuit_version,
+        hard_fork_name: req.hard_fork_name,
+        status,
+        created_at: stats.created_at,
+        started_at: stats.started_at,
+        finished_at: stats.finished_at,
+        compute_time_sec: stats.compute_time_sec,
+        input: echo_input.then_some(req.input),
+        proof: resp.proof.map(|proof| proof.get().to_string()),
+        vk: resp.verification_key.map(|vk| vk.verification_key),
+        error: resp.error,
+    };
+    (response, metadata)
+}
+
+// Drop the compute and finish times of a task that has not finished.  Sindri may report the compute
+// time so far while proving, which consumers of the SDK responses would take as final; it remains
+// available in `TaskMetadata::compute_time_sec`.
+fn finished_stats(mut stats: ProvingStats, status: &TaskStatus) -> ProvingStats {
+    if !matches!(status, TaskStatus::Success | TaskStatus::Failed) {
+        stats.compute_time_sec = None;
+        stats.finished_at = None;
+    }
+    stats
+}
+
+// Response for a submission answered by `prove_dedup_window_sec` with the earlier task, echoing
+// `input` as remembered for that task.
+fn resubmission_response(
+    req: ProveRequest,
+    task_id: String,
+    input: Option<String>,
+) -> ProveResponse {
+    ProveResponse {
+        task_id,
+        circuit_type: req.circuit_type,
+        circuit_version: req.circuit_version,
+        hard_fork_name: req.hard_fork_name,
+        status: TaskStatus::Queued,
+        created_at: 0.0,
+        started_at: None,
+        finished_at: None,
+        compute_time_sec: None,
+        input,
+        proof: None,
+        vk: None,
+        error: None,
+    }
+}
+
+fn build_prove_error_response(req: &ProveRequest, error_msg: &str) -> ProveResponse {
+    ProveResponse {
+        task_id: String::new(),
+        circuit_type: req.circuit_type,
+        circuit_version: req.circuit_version.clone(),
+        hard_fork_name: req.hard_fork_name.clone(),
+        status: TaskStatus::Failed,
+        created_at: 0.0,
+        started_at: None,
+        finished_at: None,
+        compute_time_sec: None,
+        input: Some(req.input.clone()),
+        proof: None,
+        vk: None,
+        error: Some(error_msg.to_string()),
+    }
+}
+
+// Check the input against the shape expected for its circuit type, so that malformed input is
+// rejected locally instead of costing a round-trip to Sindri.
+pub fn validate_prove_input(circuit_type: CircuitType, input: &str) -> anyhow::Result<()> {
+    let input: serde_json::Value = from_str_unbounded(input)?;
+    match circuit_type {
+        CircuitType::Chunk => ensure!(
+            input.as_array().is_some_and(|traces| !traces.is_empty()),
+            "chunk input must be a non-empty array of block traces"
+        ),
+        CircuitType::Batch => {
+            for field in ["chunk_proofs", "batch_header"] {
+                ensure!(
+                    input.get(field).is_some(),
+                    "batch input is missing `{field}`"
+                );
+            }
+        }
+        CircuitType::Bundle => ensure!(
+            input.get("batch_proofs").is_some(),
+            "bundle input is missing `batch_proofs`"
+        ),
+        CircuitType::Undefined => bail!(CloudProverError::UndefinedCircuitType),
+    }
+    Ok(())
+}
+
+// Transform the coordinator's task input into the payload Sindri expects for each circuit type.
+pub fn reprocess_prove_input(circuit_type: CircuitType, input: &str) -> anyhow::Result<String> {
+    match circuit_type {
+        // Chunk and batch inputs are submitted as-is.
+        CircuitType::Chunk | CircuitType::Batch => Ok(input.to_string()),
+        // Remove the "batch_proofs" layer because Sindri API expects the inner array as the input directly
+        CircuitType::Bundle => {
+            let bundle_task_detail: prover_darwin_v2::BundleProvingTask =
+                serde_json::from_str(input)?;
+            Ok(serde_json::to_string(&bundle_task_detail.batch_proofs)?)
+        }
+        CircuitType::Undefined => bail!(CloudProverError::UndefinedCircuitType),
+    }
+}
+
+// Task id of the synthetic responses returned by `prove` in dry-run mode.
+pub const DRY_RUN_TASK_ID: &str = "dry-run";
+
+// Namespace used when `circuit_namespace` is absent from the config.
+pub(crate) const DEFAULT_CIRCUIT_NAMESPACE: &str = "scroll-tech";
+
+// Circuit version used when `circuit_version` is absent from the config.
+const DEFAULT_CIRCUIT_VERSION: &str = "v0.13.1";
+
+// Characters of a body logged when `log_body_max_chars` is absent from the config.
+const DEFAULT_LOG_BODY_MAX_CHARS: usize = 2000;
+
+// Seconds allowed for connecting to Sindri when `connect_timeout_sec` is absent from the config.
+const DEFAULT_CONNECT_TIMEOUT_SEC: u64 = 10;
+
+// Seconds requests are refused for once the circuit breaker opens, unless configured otherwise.
+const DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SEC: u64 = 30;
+
+// Retry and timeout settings applied by `CloudProverConfigBuilder` when not set explicitly.
+pub const DEFAULT_RETRY_COUNT: u32 = 3;
+pub const DEFAULT_RETRY_WAIT_TIME_SEC: u64 = 5;
+pub const DEFAULT_CONNECTION_TIMEOUT_SEC: u64 = 60;
+
+// Default Sindri API client path. This is the base path for all
+// Sindri API calls in this version of the Sindri Scroll SDK.
+const SINDRI_API_PATH: &str = "/api/v1/";
+
+impl CloudProver {
+    pub fn new(cfg: CloudProverConfig) -> Self {
+        Self::try_new(cfg).expect("cannot build cloud prover")
+    }
+
+    // Build the prover and confirm that Sindri is reachable and accepts the API key, so that a bad
+    // deployment fails at startup instead of on its first proof.
+    pub async fn connect(cfg: CloudProverConfig) -> Result<Self, CloudProverError> {
+        let prover = Self::try_new(cfg)?;
+        prover.ping().await?;
+        Ok(prover)
+    }
+
+    pub fn try_new(cfg: CloudProverConfig) -> Result<Self, CloudProverError> {
+        cfg.validate()?;
+
+        let retry_wait_duration = Duration::from_secs(cfg.retry_wait_time_sec);
+        let min_retry_interval = retry_wait_duration / 2;
+        let max_retry_interval = cfg
+            .max_retry_interval_sec
+            .map_or(retry_wait_duration, Duration::from_secs)
+            .max(min_retry_interval);
+        let retry = |max_retries| {
+            let policy = ExponentialBackoff::builder()
+                .retry_bounds(min_retry_interval, max_retry_interval)
+                .jitter(cfg.retry_jitter.into())
+                .build_with_max_retries(max_retries);
+            RetryTransientMiddleware::new_with_policy_and_strategy(policy, SindriRetryableStrategy)
+        };
+        let post_retry_count = cfg.post_retry_count.unwrap_or(cfg.retry_count);
+        let log_prefix = cfg.log_prefix();
+        // Responses are decompressed by `DecodedBody` rather than by reqwest, so that their size on
+        // the wire can be logged.
+        let mut http_client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(cfg.connect_timeout_sec))
+            .user_agent(cfg.user_agent())
+            .default_headers(cfg.default_header_map()?);
+        if let Some(max_idle) = cfg.pool_max_idle_per_host {
+            http_client = http_client.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(idle_timeout) = cfg.pool_idle_timeout_sec {
+            http_client = http_client.pool_idle_timeout(Duration::from_secs(idle_timeout));
+        }
+        if let Some(identity) = cfg.identity()? {
+            http_client = http_client.identity(identity);
+        }
+        if let Some(proxy) = cfg.proxy()? {
+            http_client = http_client.proxy(proxy);
+        }
+        let client_builder = ClientBuilder::new(http_client.build()?)
+            .with(PostOrOtherMiddleware::new(
+                retry(post_retry_count),
+                retry(cfg.retry_count),
+            ))
+            .with(AttemptCountMiddleware)
+            .with(PostOrOtherMiddleware::new(
+                RetryAfterMiddleware::new(post_retry_count).with_log_prefix(&log_prefix),
+                RetryAfterMiddleware::new(cfg.retry_count).with_log_prefix(&log_prefix),
+            ));
+        let client = match cfg.request_compression {
+            #[cfg(feature = "zstd")]
+            RequestCompression::Zstd => client_builder.with(ZstdRequestCompressionMiddleware::new(
+                cfg.compression_min_body_size,
+            )),
+            #[cfg(not(feature = "zstd"))]
+            RequestCompression::Zstd => unreachable!("rejected by `validate` without zstd"),
+            RequestCompression::Gzip => client_builder.with(GzipRequestCompressionMiddleware::new(
+                cfg.compression_min_body_size,
+            )),
+            RequestCompression::None => client_builder,
+        }
+        .build();
+        let request_compression = (cfg.request_compression, cfg.compression_min_body_size);
+        let mut prover = Self::with_client(cfg, client)?;
+        prover.request_compression = Some(request_compression);
+        Ok(prover)
+    }
+
+    // Same as `try_new`, sending requests through `client` instead of one built from `cfg`, e.g. to
+    // add middleware of the caller's own.  The config's client settings (timeouts other than the
+    // per-request ones, retries, compression, proxy, certificates and default headers) are then up
+    // to `client`.  Retries are only counted if it includes `AttemptCountMiddleware`.
+    pub fn with_client(
+        cfg: CloudProverConfig,
+        client: ClientWithMiddleware,
+    ) -> Result<Self, CloudProverError> {
+        cfg.validate()?;
+        let log_prefix = cfg.log_prefix();
+
+        let ping_circuit_type = cfg
+            .sdk_config
+            .prover
+            .circuit_types
+            .iter()
+            .copied()
+            .find(|circuit_type| *circuit_type != CircuitType::Undefined)
+            .unwrap_or(CircuitType::Chunk);
+
+        let mut circuit_versions = vec![cfg.circuit_version];
+        for version in cfg.supported_circuit_versions {
+            if !circuit_versions.contains(&version) {
+                circuit_versions.push(version);
+            }
+        }
+
+        // The API path is joined below the path of `base_url`, so that e.g. a gateway mounted at
+        // `https://host/sindri` keeps its prefix whether or not either ends in a slash.  Method
+        // paths are in turn joined onto the API path, which therefore has to end with a slash.
+        let mut base_url = Url::parse(&cfg.base_url)?;
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+        let api_url = match cfg.api_path.trim_matches('/') {
+            "" => base_url,
+            api_path => base_url.join(&format!("{api_path}/"))?,
+        };
+
+        Ok(Self {
+            base_url: api_url,
+            circuit_namespace: cfg.circuit_namespace,
+            credentials: Arc::new(RwLock::new(Arc::new(StaticCredentialProvider::new(
+                cfg.api_key,
+            )))),
+            signer: None,
+            request_compression: None,
+            circuit_versions,
+            strict_version_check: cfg.strict_version_check,
+            send_timeout: Duration::from_secs(cfg.connection_timeout_sec),
+            method_timeouts: cfg
+                .per_method_timeout_sec
+                .into_iter()
+                .map(|(method, sec)| (method, Duration::from_secs(sec)))
+                .collect(),
+            client,
+            metrics: Arc::new(NoopMetricsRecorder),
+            retries: Arc::new(AtomicU64::new(0)),
+            in_flight: cfg
+                .max_in_flight_requests
+                .map(|limit| Arc::new(Semaphore::new(limit))),
+            breaker: cfg.circuit_breaker_threshold.map(|threshold| {
+                Arc::new(Mutex::new(CircuitBreaker::new(
+                    threshold,
+                    Duration::from_secs(cfg.circuit_breaker_cooldown_sec),
+                    log_prefix.clone(),
+                )))
+            }),
+            rate_limit: Arc::new(Mutex::new(None)),
+            active_requests: Arc::new(Semaphore::new(SHUTDOWN_PERMITS as usize)),
+            vk_cache_ttl: cfg.vk_cache_ttl_sec.map(Duration::from_secs),
+            vk_cache: Arc::new(Mutex::new(HashMap::new())),
+            ping_circuit_type,
+            perform_verify: cfg.perform_verify,
+            proof_input_field: cfg.proof_input_field,
+            extra_prove_fields: cfg.extra_prove_fields,
+            task_inputs: Arc::new(Mutex::new(TaskInputs::new(cfg.input_echo_capacity))),
+            task_states: Arc::new(Mutex::new(TaskStates::new(TRACKED_TASKS_CAPACITY))),
+            max_response_bytes: cfg.max_response_bytes,
+            prove_dedup_window: cfg.prove_dedup_window_sec.map(Duration::from_secs),
+            recent_submissions: Arc::new(Mutex::new(HashMap::new())),
+            max_input_bytes: cfg.max_input_bytes,
+            log_bodies: cfg.log_bodies,
+            log_body_max_chars: cfg.log_body_max_chars,
+            log_prefix,
+            dry_run: cfg.dry_run,
+        })
+    }
+
+    // Same as `ProvingService::prove`, additionally returning Sindri-specific task details.
+    pub async fn prove_with_metadata(&self, req: ProveRequest) -> (ProveResponse, TaskMetadata) {
+        self.submit_proof(req, None, None).await
+    }
+
+    // Same as `prove_with_metadata`, tagging the proof with a client-supplied reference, e.g. a
+    // chunk or batch identifier, that `query_by_reference` can later resolve.
+    pub async fn prove_with_reference(
+        &self,
+        req: ProveRequest,
+        reference: &str,
+    ) -> (ProveResponse, TaskMetadata) {
+        self.submit_proof(req, Some(reference), None).await
+    }
+
+    // Same as `prove_with_metadata`, adding circuit-specific proving options, e.g. a proving tier,
+    // to the body of this prove request.  They override `perform_verify` and the configured
+    // `extra_prove_fields`, but not the proof input.
+    pub async fn prove_with_options(
+        &self,
+        req: ProveRequest,
+        options: HashMap<String, serde_json::Value>,
+    ) -> (ProveResponse, TaskMetadata) {
+        self.submit_proof(req, None, Some(options)).await
+    }
+
+    async fn submit_proof(
+        &self,
+        req: ProveRequest,
+        reference: Option<&str>,
+        options: Option<HashMap<String, serde_json::Value>>,
+    ) -> (ProveResponse, TaskMetadata) {
+        if let Err(e) = self.check_circuit_version(&req.circuit_version) {
+            return (
+                build_prove_error_response(&req, &e.to_string()),
+                TaskMetadata::default(),
+            );
+        };
+
+        if let Err(e) = validate_prove_input(req.circuit_type, &req.input) {
+            return (
+                build_prove_error_response(&req, &format!("Invalid proof input: {}", e)),
+                TaskMetadata::default(),
+            );
+        }
+
+        let input = match reprocess_prove_input(req.circuit_type, &req.input) {
+            Ok(input) => input,
+            Err(e) => {
+                return (
+                    build_prove_error_response(&req, &e.to_string()),
+                    TaskMetadata::default(),
+                )
+            }
+        };
+
+        let input_hash = input_sha256(&input);
+        if let Err(e) = self.check_input_size(&input) {
+            return (
+                build_prove_error_response(&req, &e.to_string()),
+                TaskMetadata::default(),
+            );
+        }
+        let sindri_req = self.prove_request_body(input, reference, options);
+
+        if self.dry_run {
+            return (
+                self.dry_run_response(req, &sindri_req),
+                TaskMetadata::default(),
+            );
+        }
+
+        let key = (
+            input_hash.clone(),
+            format!("{:?}", req.circuit_type),
+            req.circuit_version.clone(),
+        );
+        let pending = match self.claim_submission(key).await {
+            Some(Submission::Earlier(task_id)) => {
+                log::info!(
+                    "{}, input was already submitted as {task_id}, not submitting again",
+                    self.log_prefix
+                );
+                let input = self.task_inputs.lock().unwrap().get(&task_id);
+                return (
+                    resubmission_response(req, task_id, input),
+                    TaskMetadata::default(),
+                );
+            }
+            Some(Submission::Claimed(pending)) => Some(pending),
+            None => None,
+        };
+
+        match self
+            .post_with_token::<_, SindriProofInfoResponse>(
+                MethodClass::Circuit(req.circuit_type, req.circuit_version.clone()),
+                "prove",
+                &sindri_req,
+                idempotency_headers(),
+            )
+            .await
+        {
+            Ok((resp, http_status, elapsed)) => {
+                self.track_status(&resp);
+                if let Some(pending) = pending {
+                    pending.complete(resp.task_id());
+                }
+                self.task_inputs
+                    .lock()
+                    .unwrap()
+                    .insert(resp.task_id(), &req.input);
+                log::info!(
+                    "{}, submitted task {} with input sha256 {input_hash}",
+                    self.log_prefix,
+                    resp.task_id()
+                );
+                let (response, mut metadata) =
+                    prove_response(&self.log_prefix, resp, http_status, elapsed, req, true);
+                metadata.input_sha256 = Some(input_hash);
+                (response, metadata)
+            }
+            Err(e) => (
+                build_prove_error_response(&req, &format!("Failed to request proof: {}", e)),
+                TaskMetadata::default(),
+            ),
+        }
+    }
+
+    // Submit a prove request body that is already compressed with `encoding`, e.g. one staged
+    // compressed on disk, without inflating it.  The body must be the complete JSON request as
+    // Sindri expects it, i.e. `proof_input` (with bundle inputs already unwrapped to their
+    // `batch_proofs`) and `perform_verify`.  It is sent unchanged: the input is neither validated
+    // nor reprocessed, and the request compression middleware leaves it alone.  The input of
+    // `req` is ignored, and is not remembered for echoing.
+    pub async fn prove_precompressed(
+        &self,
+        req: ProveRequest,
+        body: Vec<u8>,
+        encoding: RequestCompression,
+    ) -> (ProveResponse, TaskMetadata) {
+        if let Err(e) = self.check_circuit_version(&req.circuit_version) {
+            return (
+                build_prove_error_response(&req, &e.to_string()),
+                TaskMetadata::default(),
+            );
+        };
+        if self.dry_run {
+            return (
+                self.dry_run_submission(req, body.len(), false),
+                TaskMetadata::default(),
+            );
+        }
+
+        let mut headers = idempotency_headers();
+        if let Some(encoding) = encoding.content_encoding() {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        }
+        match self
+            .send_with_token::<SindriProofInfoResponse>(
+                MethodClass::Circuit(req.circuit_type, req.circuit_version.clone()),
+                "prove",
+                Method::POST,
+                None,
+                headers,
+                Some(body),
+            )
+            .await
+        {
+            Ok((resp, http_status, elapsed)) => {
+                self.track_status(&resp);
+                prove_response(&self.log_prefix, resp, http_status, elapsed, req, false)
+            }
+            Err(e) => (
+                build_prove_error_response(&req, &format!("Failed to request proof: {}", e)),
+                TaskMetadata::default(),
+            ),
+        }
+    }
+
+    // Label an error reported through `ProvingService` with this client's log prefix, once; the
+    // messages of `CloudProverError` carry none.
+    fn label_error(&self, error: Option<String>) -> Option<String> {
+        error.map(|error| format!("{}, {error}", self.log_prefix))
+    }
+
+    // Report a change of the task's status since the previous response for it, with how long the
+    // previous status lasted.
+    fn track_status(&self, resp: &SindriProofInfoResponse) {
+        let previous = self
+            .task_states
+            .lock()
+            .unwrap()
+            .observe(resp.task_id(), &resp.status);
+        let Some((from, elapsed)) = previous else {
+            return;
+        };
+        log::info!(
+            "{}, task {}: {} -> {} after {:.1}s",
+            self.log_prefix,
+            resp.task_id(),
+            String::from(from.clone()),
+            String::from(resp.status.clone()),
+            elapsed.as_secs_f64()
+        );
+        trace_event!(
+            task_id = resp.task_id(),
+            from = ?from,
+            to = ?resp.status,
+            elapsed_ms = elapsed.as_millis() as u64,
+            "task status changed"
+        );
+        self.metrics
+            .record_transition(resp.task_id(), &from, &resp.status, elapsed);
+    }
+
+    // Look up a submission in the dedup window, forgetting submissions that fell out of it, or
+    // `None` without a dedup window.  A submission of the same input still in flight is waited
+    // for; should it fail, this one is claimed in its place.
+    async fn claim_submission(&self, key: SubmissionKey) -> Option<Submission> {
+        let window = self.prove_dedup_window?;
+        loop {
+            let mut in_flight = {
+                let mut recent = self.recent_submissions.lock().unwrap();
+                recent.retain(|_, entry| match entry {
+                    RecentSubmission::Submitted(_, submitted_at) => submitted_at.elapsed() < window,
+                    RecentSubmission::Pending(_) => true,
+                });
+                match recent.get(&key) {
+                    Some(RecentSubmission::Submitted(task_id, _)) => {
+                        return Some(Submission::Earlier(task_id.clone()))
+                    }
+                    Some(RecentSubmission::Pending(receiver)) => receiver.clone(),
+                    None => {
+                        let (sender, receiver) = tokio::sync::watch::channel(None);
+                        recent.insert(key.clone(), RecentSubmission::Pending(receiver));
+                        return Some(Submission::Claimed(PendingSubmission {
+                            recent: self.recent_submissions.clone(),
+                            key,
+                            sender,
+                        }));
+                    }
+                }
+            };
+            if in_flight.changed().await.is_ok() {
+                if let Some(task_id) = in_flight.borrow().clone() {
+                    return Some(Submission::Earlier(task_id));
+                }
+            }
+        }
+    }
+
+    // Submit several proofs with at most `concurrency` submissions in flight, returning the
+    // responses in the order of `reqs`.
+    pub async fn prove_many(
+        &self,
+        reqs: Vec<ProveRequest>,
+        concurrency: usize,
+    ) -> Vec<ProveResponse> {
+        futures::stream::iter(reqs.into_iter().map(|req| self.prove(req)))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    // Body of a prove request: the proof input and verification flag, the reference if any, plus
+    // any configured extra fields and per-request options.
+    fn prove_request_body(
+        &self,
+        input: String,
+        reference: Option<&str>,
+        options: Option<HashMap<String, serde_json::Value>>,
+    ) -> serde_json::Map<String, serde_json::Value> {
+        let mut body = serde_json::Map::new();
+        body.insert("perform_verify".to_string(), self.perform_verify.into());
+        if let Some(reference) = reference {
+            body.insert(
+                "meta".to_string(),
+                serde_json::json!({ REFERENCE_META_KEY: reference }),
+            );
+        }
+        body.extend(self.extra_prove_fields.clone());
+        body.extend(options.into_iter().flatten());
+        body.insert(self.proof_input_field.clone(), input.into());
+        body
+    }
+
+    fn check_input_size(&self, input: &str) -> Result<(), CloudProverError> {
+        match self.max_input_bytes {
+            Some(limit) if input.len() > limit => Err(CloudProverError::InputTooLarge {
+                size: input.len(),
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Exercise the remaining request-building steps of `prove` without sending anything.
+    fn dry_run_response<Req: Serialize>(
+        &self,
+        req: ProveRequest,
+        sindri_req: &Req,
+    ) -> ProveResponse {
+        match serde_json::to_vec(sindri_req) {
+            Ok(body) => self.dry_run_submission(req, body.len(), true),
+            Err(e) => build_prove_error_response(&req, &CloudProverError::from(e).to_string()),
+        }
+    }
+
+    // Response to a dry-run submission of a `body_len` byte body, once the URL it would be sent to
+    // is built.  The input of `req` is echoed if `echo_input` is set.
+    fn dry_run_submission(
+        &self,
+        req: ProveRequest,
+        body_len: usize,
+        echo_input: bool,
+    ) -> ProveResponse {
+        let url = match self.build_url(
+            MethodClass::Circuit(req.circuit_type, req.circuit_version.clone()),
+            "prove",
+            None,
+        ) {
+            Ok(url) => url,
             Err(e) => return build_prove_error_response(&req, &e.to_string()),
         };
+        log::info!(
+            "{}, dry run, skipped submitting {body_len} bytes to {url}",
+            self.log_prefix
+        );
 
-        #[derive(serde::Deserialize, serde::Serialize)]
-        struct SindriProveRequest {
-            proof_input: String,
-            perform_verify: bool,
+        ProveResponse {
+            task_id: DRY_RUN_TASK_ID.to_string(),
+            circuit_type: req.circuit_type,
+            circuit_version: req.circuit_version,
+            hard_fork_name: req.hard_fork_name,
+            status: TaskStatus::Queued,
+            created_at: 0.0,
+            started_at: None,
+            finished_at: None,
+            compute_time_sec: None,
+            input: echo_input.then_some(req.input),
+            proof: None,
+            vk: None,
+            error: None,
         }
+    }
 
-        let sindri_req = SindriProveRequest {
-            proof_input: input,
-            perform_verify: true,
-        };
-
-        match self
-            .post_with_token::<SindriProveRequest, SindriProofInfoResponse>(
-                MethodClass::Circuit(req.circuit_type),
-                "prove",
-                &sindri_req,
-            )
+    // Same as `ProvingService::query_task`, additionally returning Sindri-specific task details.
+    pub async fn query_task_with_metadata(
+        &self,
+        req: QueryTaskRequest,
+    ) -> (QueryTaskResponse, TaskMetadata) {
+        self.query_task_with_options(req, QueryOptions::full())
             .await
-        {
-            Ok(resp) => {
-                let (created_at, started_at, finished_at) = proving_timestamps_from_response(&resp);
-                ProveResponse {
-                    task_id: resp.proof_id,
-                    circuit_type: req.circuit_type,
-                    circuit_version: req.circuit_version,
-                    hard_fork_name: req.hard_fork_name,
-                    status: resp.status.into(),
-                    created_at,
-                    started_at,
-                    finished_at,
-                    compute_time_sec: resp.compute_time_sec,
-                    input: Some(req.input.clone()),
-                    proof: serde_json::to_string(&resp.proof).ok(),
-                    vk: resp.verification_key.map(|vk| vk.verification_key),
-                    error: resp.error,
-                }
-            }
-            Err(e) => {
-                return build_prove_error_response(&req, &format!("Failed to request proof: {}", e))
-            }
-        }
     }
 
-    async fn query_task(&self, req: QueryTaskRequest) -> QueryTaskResponse {
-        let query_params: HashMap<String, String> = [
-            ("include_proof", "true"),
-            ("include_public", "true"),
-            ("include_verification_key", "true"),
-        ]
-        .iter()
-        .map(|&(k, v)| (k.to_string(), v.to_string()))
-        .collect();
+    // Same as `query_task_with_metadata`, fetching only the optional fields selected by `options`.
+    pub async fn query_task_with_options(
+        &self,
+        req: QueryTaskRequest,
+        options: QueryOptions,
+    ) -> (QueryTaskResponse, TaskMetadata) {
+        let query_params = options.query_params();
 
         match self
-            .get_with_token::<SindriProofInfoResponse>(
+            .send_with_token::<SindriProofInfoResponse>(
                 MethodClass::Proof(req.task_id.clone()),
                 "detail",
+                Method::GET,
                 Some(query_params),
+                HeaderMap::new(),
+                None,
             )
             .await
         {
-            Ok(resp) => {
-                let (created_at, started_at, finished_at) = proving_timestamps_from_response(&resp);
-                QueryTaskResponse {
-                    task_id: resp.proof_id,
-                    circuit_type: CircuitType::Undefined, // TODO:
-                    circuit_version: "".to_string(),
+            Ok((resp, _, elapsed)) => {
+                self.track_status(&resp);
+                let stats = proving_timestamps_from_response(&self.log_prefix, &resp);
+                let mut metadata = TaskMetadata::from_response(&resp);
+                metadata.round_trip = Some(elapsed);
+                let input = self.task_inputs.lock().unwrap().get(resp.task_id());
+                warn_unexpected_status(&self.log_prefix, &resp, options.include_proof);
+                let DetailedTaskStatus { status, detail } =
+                    resp.detailed_status(options.include_proof);
+                metadata.status_detail = detail;
+                let stats = finished_stats(stats, &status);
+                let (circuit_type, circuit_version) = resp
+                    .circuit_name
+                    .as_deref()
+                    .and_then(|name| parse_circuit_slug(name).ok())
+                    .unwrap_or((CircuitType::Undefined, String::new()));
+                let response = QueryTaskResponse {
+                    task_id: resp.task_id().to_string(),
+                    circuit_type,
+                    circuit_version,
                     hard_fork_name: "".to_string(),
-                    status: resp.status.into(),
-                    created_at,
-                    started_at,
-                    finished_at,
-                    compute_time_sec: resp.compute_time_sec,
-                    input: None,
-                    proof: serde_json::to_string(&resp.proof).ok(),
+                    status,
+                    created_at: stats.created_at,
+                    started_at: stats.started_at,
+                    finished_at: stats.finished_at,
+                    compute_time_sec: stats.compute_time_sec,
+                    input,
+                    proof: resp.proof.map(|proof| proof.get().to_string()),
                     vk: resp.verification_key.map(|vk| vk.verification_key),
                     error: resp.error,
-                }
+                };
+                (response, metadata)
             }
             Err(e) => {
-                log::error!("Failed to query proof: {:?}", e);
-                QueryTaskResponse {
+                log::error!("{}, failed to query proof: {:?}", self.log_prefix, e);
+                let response = QueryTaskResponse {
                     task_id: req.task_id,
                     circuit_type: CircuitType::Undefined,
                     circuit_version: "".to_string(),
@@ -299,73 +2546,521 @@ impl ProvingService for CloudProver {
                     proof: None,
                     vk: None,
                     error: Some(format!("Failed to query proof: {}", e)),
+                };
+                (response, TaskMetadata::default())
+            }
+        }
+    }
+
+    // Obtain the bearer token from the given provider instead of the configured `api_key`.
+    pub fn with_credential_provider(mut self, credentials: Arc<dyn CredentialProvider>) -> Self {
+        self.credentials = Arc::new(RwLock::new(credentials));
+        self
+    }
+
+    // Add the headers computed by `signer` to every request.
+    pub fn with_request_signer(mut self, signer: Arc<dyn RequestSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    // Send `api_key` from now on, replacing the configured key or credential provider of this
+    // prover and of its clones.  Requests already sent are unaffected, and the connection pool is
+    // kept.
+    pub fn set_api_key(&self, api_key: String) {
+        *self.credentials.write().unwrap() = Arc::new(StaticCredentialProvider::new(api_key));
+    }
+
+    // Poll a task every `poll_interval` until it succeeds or fails, giving up after `max_wait` with
+    // a `TaskTimeout` carrying the last status seen.  Queued and proving tasks, as well as failed
+    // polls, are waited on.  Polls fetch only the status; the proof, public inputs and vk are
+    // fetched once the task has succeeded.
+    pub async fn wait_for_task(
+        &self,
+        task_id: &str,
+        poll_interval: Duration,
+        max_wait: Duration,
+    ) -> Result<QueryTaskResponse, CloudProverError> {
+        let deadline = Instant::now() + max_wait;
+        let request = || QueryTaskRequest {
+            task_id: task_id.to_string(),
+        };
+        let mut last_status = None;
+        loop {
+            let (resp, metadata) = self
+                .query_task_with_options(request(), QueryOptions::default())
+                .await;
+            // Failed polls carry no status and leave the last one in place.
+            last_status = metadata.sindri_status.or(last_status);
+            match resp.status {
+                TaskStatus::Success => {
+                    // Keep polling if the proof is not available yet or could not be fetched.
+                    let resp = self.query_task(request()).await;
+                    if matches!(resp.status, TaskStatus::Success | TaskStatus::Failed) {
+                        return Ok(resp);
+                    }
                 }
+                TaskStatus::Failed => return Ok(resp),
+                _ => {}
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(CloudProverError::TaskTimeout {
+                    task_id: task_id.to_string(),
+                    max_wait,
+                    last_status,
+                });
             }
+            tokio::time::sleep(poll_interval.min(deadline - now)).await;
         }
     }
-}
 
-fn build_prove_error_response(req: &ProveRequest, error_msg: &str) -> ProveResponse {
-    ProveResponse {
-        task_id: String::new(),
-        circuit_type: req.circuit_type,
-        circuit_version: req.circuit_version.clone(),
-        hard_fork_name: req.hard_fork_name.clone(),
-        status: TaskStatus::Failed,
-        created_at: 0.0,
-        started_at: None,
-        finished_at: None,
-        compute_time_sec: None,
-        input: Some(req.input.clone()),
-        proof: None,
-        vk: None,
-        error: Some(error_msg.to_string()),
+    // Ask Sindri to verify the proof of a finished task against its verification key and public
+    // inputs.  Fails with `ProofUnavailable` if the task has not produced a proof.
+    pub async fn verify_proof(&self, task_id: &str) -> Result<bool, CloudProverError> {
+        // Only the status is needed; Sindri verifies the proof it holds.
+        let query_params = QueryOptions::default().query_params();
+        let detail = self
+            .get_with_token::<SindriProofInfoResponse>(
+                MethodClass::Proof(task_id.to_string()),
+                "detail",
+                Some(query_params),
+            )
+            .await?;
+        if detail.status != SindriTaskStatus::Success {
+            return Err(CloudProverError::ProofUnavailable {
+                task_id: task_id.to_string(),
+            });
+        }
+
+        #[derive(serde::Deserialize)]
+        struct SindriProofVerifyResponse {
+            success: bool,
+            message: Option<String>,
+        }
+
+        let resp = self
+            .get_with_token::<SindriProofVerifyResponse>(
+                MethodClass::Proof(task_id.to_string()),
+                "verify",
+                None,
+            )
+            .await?;
+        if !resp.success {
+            log::warn!(
+                "{}, proof {task_id} failed verification: {}",
+                self.log_prefix,
+                resp.message.unwrap_or_default()
+            );
+        }
+        Ok(resp.success)
     }
-}
 
-// Remove the "batch_proofs" layer because Sindri API expects the inner array as the input directly
-fn reprocess_prove_input(req: &ProveRequest) -> anyhow::Result<String> {
-    if req.circuit_type == CircuitType::Bundle {
-        let bundle_task_detail: prover_darwin_v2::BundleProvingTask =
-            serde_json::from_str(&req.input)?;
-        Ok(serde_json::to_string(&bundle_task_detail.batch_proofs)?)
-    } else {
-        Ok(req.input.clone())
+    // Write the proof of a finished task to `writer`, exactly as `query_task` would return it,
+    // without the public inputs and vk.  The proof is not streamed: the whole response is read
+    // first, keeping the proof in memory as the bytes Sindri sent, and only then written out.
+    // Fails with `ProofUnavailable` if the task has not produced a proof.
+    pub async fn download_proof<W: AsyncWrite + Unpin>(
+        &self,
+        task_id: &str,
+        mut writer: W,
+    ) -> Result<(), CloudProverError> {
+        let query_params = QueryOptions {
+            include_proof: true,
+            ..Default::default()
+        }
+        .query_params();
+        let detail = self
+            .get_with_token::<SindriProofInfoResponse>(
+                MethodClass::Proof(task_id.to_string()),
+                "detail",
+                Some(query_params),
+            )
+            .await?;
+        let proof = match detail.proof {
+            Some(proof) if detail.status == SindriTaskStatus::Success => proof,
+            _ => {
+                return Err(CloudProverError::ProofUnavailable {
+                    task_id: task_id.to_string(),
+                })
+            }
+        };
+        writer
+            .write_all(proof.get().as_bytes())
+            .await
+            .map_err(CloudProverError::ProofWrite)?;
+        writer.flush().await.map_err(CloudProverError::ProofWrite)
     }
-}
 
-// alternatively, we can just read it from the config
-const THIS_CIRCUIT_VERSION: &str = "v0.13.1";
+    // Fetch the verification key of the circuit that produced a task's proof, in Scroll's
+    // encoding, without downloading the proof itself.  Fails with `VkUnavailable` if Sindri returns
+    // no key.
+    pub async fn get_proof_vk(&self, task_id: &str) -> Result<String, CloudProverError> {
+        let query_params = QueryOptions {
+            include_vk: true,
+            ..Default::default()
+        }
+        .query_params();
+        let detail = self
+            .get_with_token::<SindriProofInfoResponse>(
+                MethodClass::Proof(task_id.to_string()),
+                "detail",
+                Some(query_params),
+            )
+            .await?;
+        let vk = detail
+            .verification_key
+            .ok_or_else(|| CloudProverError::VkUnavailable {
+                task_id: task_id.to_string(),
+            })?;
+        reformat_vk(vk.verification_key)
+    }
 
-// Sindri API client path. This is the base path for all
-// Sindri API calls in this version of the Sindri Scroll SDK.
-const SINDRI_API_PATH: &str = "/api/v1/";
+    // Stop sending requests, and wait up to `grace` for the requests already sent to complete.
+    // Requests made meanwhile wait and then, like all later ones, fail with `ShutDown`.  Applies to
+    // the clones of this prover too.  Returns whether every request completed in time.
+    pub async fn shutdown(&self, grace: Duration) -> bool {
+        let drained =
+            tokio::time::timeout(grace, self.active_requests.acquire_many(SHUTDOWN_PERMITS)).await;
+        self.active_requests.close();
+        let drained = matches!(drained, Ok(Ok(_)));
+        if !drained {
+            log::warn!(
+                "{}, shut down with requests still in flight after {grace:?}",
+                self.log_prefix
+            );
+        }
+        drained
+    }
 
-impl CloudProver {
-    pub fn new(cfg: CloudProverConfig) -> Self {
-        let retry_wait_duration = Duration::from_secs(cfg.retry_wait_time_sec);
-        let retry_policy = ExponentialBackoff::builder()
-            .retry_bounds(retry_wait_duration / 2, retry_wait_duration)
-            .build_with_max_retries(cfg.retry_count);
-        let client = ClientBuilder::new(
-            // Explicitly enable zstd response compression.
-            reqwest::Client::builder().zstd(true).build().unwrap(),
+    // The rate limit Sindri reported with the latest response carrying rate-limit headers, for
+    // throttling before requests get rejected with 429.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    // The HTTP client requests are sent through.
+    pub fn client(&self) -> &ClientWithMiddleware {
+        &self.client
+    }
+
+    // Report request counts, latencies and retries to the given recorder.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsRecorder>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    // Total number of times a request to Sindri was retried since the prover was created, for
+    // spotting flaky connectivity without a `MetricsRecorder`.
+    pub fn total_retries(&self) -> u64 {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    // Fetch the metadata of the circuit serving this circuit type at the primary configured version.
+    pub async fn circuit_detail(
+        &self,
+        circuit_type: CircuitType,
+    ) -> Result<CircuitDetail, CloudProverError> {
+        self.get_with_token(
+            MethodClass::Circuit(circuit_type, self.circuit_versions[0].clone()),
+            "detail",
+            None,
         )
-        .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-        .with(ZstdRequestCompressionMiddleware)
-        .build();
+        .await
+    }
 
-        let base_url = Url::parse(&cfg.base_url).expect("cannot parse cloud prover base_url");
-        let api_url = base_url
-            .join(SINDRI_API_PATH)
-            .expect("cannot parse cloud prover api_url");
+    // Confirm that Sindri is reachable and accepts the API key, by fetching the detail of a
+    // configured circuit.  A rejected key is reported as `CloudProverError::Unauthorized`.
+    pub async fn ping(&self) -> Result<(), CloudProverError> {
+        self.get_with_token::<serde::de::IgnoredAny>(
+            MethodClass::Circuit(self.ping_circuit_type, self.circuit_versions[0].clone()),
+            "detail",
+            None,
+        )
+        .await?;
+        Ok(())
+    }
 
-        Self {
-            base_url: api_url,
-            api_key: cfg.api_key,
-            send_timeout: Duration::from_secs(cfg.connection_timeout_sec),
-            client,
+    // Cancel a proof task that is no longer needed (e.g. its chunk was reorged out), so that
+    // Sindri stops spending compute on it.
+    pub async fn cancel_task(&self, task_id: &str) -> Result<(), CloudProverError> {
+        // Sindri's API reference documents deleting a proof ("Proof Delete") as
+        // `DELETE /api/v1/proof/{proof_id}/delete`, the route its own SDKs use, rather than as a
+        // DELETE on the proof resource itself.
+        self.delete_with_token::<serde::de::IgnoredAny>(
+            MethodClass::Proof(task_id.to_string()),
+            "delete",
+        )
+        .await?;
+        Ok(())
+    }
+
+    // Same as `ProvingService::query_task` for each of `task_ids`, querying several at once.  Sindri
+    // has no bulk status endpoint, so this saves waiting rather than requests.  The responses are in
+    // the order of `task_ids`.
+    pub async fn query_tasks(&self, task_ids: &[String]) -> Vec<QueryTaskResponse> {
+        futures::stream::iter(task_ids)
+            .map(|task_id| {
+                self.query_task(QueryTaskRequest {
+                    task_id: task_id.clone(),
+                })
+            })
+            .buffered(QUERY_TASKS_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    // Resolve a reference given to `prove_with_reference` to the current state of its proof.  When
+    // the same reference was submitted several times, the most recent proof is returned.
+    pub async fn query_by_reference(
+        &self,
+        reference: &str,
+    ) -> Result<QueryTaskResponse, CloudProverError> {
+        let proofs = self
+            .list_proofs(ListProofsParams {
+                reference: Some(reference.to_string()),
+                ..Default::default()
+            })
+            .await?;
+        let latest = proofs
+            .into_iter()
+            .max_by_key(|proof| proof.created_at().ok())
+            .ok_or_else(|| CloudProverError::UnknownReference {
+                reference: reference.to_string(),
+            })?;
+        Ok(self
+            .query_task(QueryTaskRequest {
+                task_id: latest.task_id().to_string(),
+            })
+            .await)
+    }
+
+    // List proofs belonging to the account, transparently following pagination.  Useful for
+    // reconciling which tasks completed while in-memory task state was lost.
+    pub async fn list_proofs(
+        &self,
+        params: ListProofsParams,
+    ) -> Result<Vec<SindriProofInfoResponse>, CloudProverError> {
+        let filter = self.proof_list_filter(&params)?;
+
+        let mut proofs = Vec::new();
+        let mut offset = params.offset;
+        loop {
+            let page_size = match params.limit {
+                Some(limit) => limit
+                    .saturating_sub(proofs.len())
+                    .min(LIST_PROOFS_PAGE_SIZE),
+                None => LIST_PROOFS_PAGE_SIZE,
+            };
+            if page_size == 0 {
+                break;
+            }
+
+            let page = self.list_proofs_page(&filter, page_size, offset).await?;
+            // A page longer than asked for is cut short, so that `limit` holds.
+            let mut items = page.items;
+            items.truncate(page_size);
+            let received = items.len();
+            offset += received;
+            proofs.extend(items);
+            if received < page_size || offset >= page.count {
+                break;
+            }
+        }
+
+        Ok(proofs)
+    }
+
+    // Estimate how long a proof of this circuit type at the primary configured version would
+    // take, from the account's most recent successful proofs, along with how many of the
+    // account's proofs are still waiting in the queue.
+    pub async fn estimate(
+        &self,
+        circuit_type: CircuitType,
+    ) -> Result<EstimateInfo, CloudProverError> {
+        let recent = self
+            .list_proofs(ListProofsParams {
+                status: Some(SindriTaskStatus::Success),
+                circuit_type: Some(circuit_type),
+                limit: Some(ESTIMATE_SAMPLE_SIZE),
+                ..Default::default()
+            })
+            .await?;
+        let average = |values: Vec<f64>| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+
+        let queued_filter = self.proof_list_filter(&ListProofsParams {
+            status: Some(SindriTaskStatus::Queued),
+            circuit_type: Some(circuit_type),
+            ..Default::default()
+        })?;
+        let queued = self.list_proofs_page(&queued_filter, 1, 0).await?.count;
+
+        Ok(EstimateInfo {
+            sample_size: recent.len(),
+            avg_compute_time_sec: average(
+                recent.iter().filter_map(|p| p.compute_time_sec).collect(),
+            ),
+            avg_queue_time_sec: average(recent.iter().filter_map(|p| p.queue_time_sec).collect()),
+            queued,
+        })
+    }
+
+    fn proof_list_filter(&self, params: &ListProofsParams) -> Result<String, CloudProverError> {
+        #[derive(Serialize)]
+        struct SindriProofListFilter {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            status: Option<SindriTaskStatus>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            circuit_id: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            meta: Option<HashMap<&'static str, String>>,
+        }
+
+        let circuit_filter = match params.circuit_type {
+            Some(circuit_type) => {
+                let version = params
+                    .circuit_version
+                    .as_deref()
+                    .unwrap_or(&self.circuit_versions[0]);
+                Some(circuit_id(&self.circuit_namespace, circuit_type, version)?)
+            }
+            None => None,
+        };
+        Ok(serde_json::to_string(&SindriProofListFilter {
+            status: params.status.clone(),
+            circuit_id: circuit_filter,
+            meta: params
+                .reference
+                .clone()
+                .map(|reference| HashMap::from([(REFERENCE_META_KEY, reference)])),
+        })?)
+    }
+
+    async fn list_proofs_page(
+        &self,
+        filter: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<SindriProofListResponse, CloudProverError> {
+        let query_params: HashMap<String, String> = [
+            ("limit".to_string(), limit.to_string()),
+            ("offset".to_string(), offset.to_string()),
+        ]
+        .into_iter()
+        .collect();
+        self.request_with_token(
+            MethodClass::Proofs,
+            "list",
+            Method::POST,
+            Some(query_params),
+            HeaderMap::new(),
+            Some(filter.as_bytes().to_vec()),
+        )
+        .await
+    }
+
+    // Body of `ProvingService::get_vks`.
+    async fn fetch_vks(&self, req: GetVkRequest) -> GetVkResponse {
+        if let Err(e) = self.check_circuit_version(&req.circuit_version) {
+            return GetVkResponse {
+                vks: Vec::new(),
+                error: Some(e.to_string()),
+            };
+        };
+
+        // Sorted and deduplicated, so that the same set of circuits yields the same list whatever
+        // the order they were requested in.
+        let mut vks: Vec<String> = Vec::new();
+        let mut error = None;
+        for CircuitVk { circuit_type, vk } in self.vks_by_circuit(req).await {
+            match vk {
+                Ok(vk) => vks.push(vk),
+                Err(e) => {
+                    error = Some(format!("failed to fetch the {circuit_type:?} vk: {e}"));
+                    break;
+                }
+            }
+        }
+        vks.sort_unstable();
+        vks.dedup();
+
+        GetVkResponse { vks, error }
+    }
+
+    // Same as `ProvingService::get_vks`, returning the outcome for each requested circuit type
+    // instead of stopping at the first failure, and without merging circuits that share a vk.
+    pub async fn vks_by_circuit(&self, req: GetVkRequest) -> Vec<CircuitVk> {
+        // Fetch all circuit types concurrently, but report them in the requested order.
+        futures::future::join_all(req.circuit_types.iter().map(|circuit_type| async {
+            let vk = match self.check_circuit_version(&req.circuit_version) {
+                Ok(()) => self.fetch_vk(*circuit_type, &req.circuit_version).await,
+                Err(e) => Err(e),
+            };
+            CircuitVk {
+                circuit_type: *circuit_type,
+                vk,
+            }
+        }))
+        .await
+    }
+
+    // Return the verification key of a circuit in Scroll's encoding, consulting the cache first.
+    async fn fetch_vk(
+        &self,
+        circuit_type: CircuitType,
+        circuit_version: &str,
+    ) -> Result<String, CloudProverError> {
+        let key = (circuit_version.to_string(), circuit_slug(circuit_type)?);
+        if let Some((vk, fetched_at)) = self.vk_cache.lock().unwrap().get(&key) {
+            if self
+                .vk_cache_ttl
+                .map_or(true, |ttl| fetched_at.elapsed() < ttl)
+            {
+                return Ok(vk.clone());
+            }
+        }
+
+        #[derive(serde::Deserialize)]
+        struct SindriCircuitInfoResponse {
+            verification_key: VerificationKey,
+        }
+
+        let resp = self
+            .get_with_token::<SindriCircuitInfoResponse>(
+                MethodClass::Circuit(circuit_type, circuit_version.to_string()),
+                "detail",
+                None,
+            )
+            .await?;
+        let vk = reformat_vk(resp.verification_key.verification_key)?;
+
+        self.vk_cache
+            .lock()
+            .unwrap()
+            .insert(key, (vk.clone(), Instant::now()));
+        Ok(vk)
+    }
+
+    fn check_circuit_version(&self, circuit_version: &str) -> Result<(), CloudProverError> {
+        if self.circuit_versions.iter().any(|v| v == circuit_version) {
+            return Ok(());
         }
+        if self.strict_version_check {
+            return Err(CloudProverError::VersionMismatch {
+                supported: self.circuit_versions.clone(),
+                actual: circuit_version.to_string(),
+            });
+        }
+        log::warn!(
+            "{}, circuit version {circuit_version} is not one of {:?}, proceeding as \
+             strict_version_check is off",
+            self.log_prefix,
+            self.circuit_versions
+        );
+        Ok(())
     }
 
     fn build_url(
@@ -373,21 +3068,32 @@ impl CloudProver {
         method_class: MethodClass,
         method: &str,
         query_params: Option<HashMap<String, String>>,
-    ) -> anyhow::Result<Url> {
-        let method_base = match method_class {
-            MethodClass::Circuit(circuit_type) => {
-                let circuit = match circuit_type {
-                    CircuitType::Chunk => "chunk_prover",
-                    CircuitType::Batch => "batch_prover",
-                    CircuitType::Bundle => "bundle_prover",
-                    CircuitType::Undefined => unreachable!("circuit type is undefined"),
-                };
-                format!("circuit/scroll-tech/{}:{}/", circuit, THIS_CIRCUIT_VERSION)
-            }
-            MethodClass::Proof(id) => format!("proof/{}/", id),
+    ) -> Result<Url, CloudProverError> {
+        // Dynamic parts are percent-encoded as single path segments, so that e.g. a task id
+        // containing `/` or `?` cannot change which endpoint is addressed.  Dot segments would
+        // still be resolved, and empty ones collapse, so these are rejected.
+        let segments = match method_class {
+            MethodClass::Circuit(circuit_type, circuit_version) => vec![
+                "circuit".to_string(),
+                self.circuit_namespace.clone(),
+                format!("{}:{}", circuit_slug(circuit_type)?, circuit_version),
+            ],
+            MethodClass::Proof(id) => vec!["proof".to_string(), id],
+            MethodClass::Proofs => vec!["proof".to_string()],
         };
+        if let Some(segment) = segments
+            .iter()
+            .find(|segment| matches!(segment.as_str(), "" | "." | ".."))
+        {
+            return Err(CloudProverError::InvalidPathSegment(segment.clone()));
+        }
 
-        let mut url = self.base_url.join(&method_base)?.join(method)?;
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| url::ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .pop_if_empty()
+            .extend(segments)
+            .push(method);
 
         if let Some(params) = query_params {
             url.query_pairs_mut().extend_pairs(params);
@@ -396,20 +3102,29 @@ impl CloudProver {
         Ok(url)
     }
 
+    // Returns the response along with its HTTP status and round-trip time, see `send_with_token`.
     async fn post_with_token<Req, Resp>(
         &self,
         method_class: MethodClass,
         method: &str,
         req: &Req,
-    ) -> anyhow::Result<Resp>
+        headers: HeaderMap,
+    ) -> Result<(Resp, StatusCode, Duration), CloudProverError>
     where
         Req: ?Sized + Serialize,
-        Resp: serde::de::DeserializeOwned,
+        Resp: serde::de::DeserializeOwned + Send + 'static,
     {
-        let request_body = serde_json::to_string(req)?;
+        let request_body = serde_json::to_vec(req)?;
 
-        self.request_with_token(method_class, method, None, Some(request_body))
-            .await
+        self.send_with_token(
+            method_class,
+            method,
+            Method::POST,
+            None,
+            headers,
+            Some(request_body),
+        )
+        .await
     }
 
     async fn get_with_token<Resp>(
@@ -417,61 +3132,338 @@ impl CloudProver {
         method_class: MethodClass,
         method: &str,
         query_params: Option<HashMap<String, String>>,
-    ) -> anyhow::Result<Resp>
+    ) -> Result<Resp, CloudProverError>
     where
-        Resp: serde::de::DeserializeOwned,
+        Resp: serde::de::DeserializeOwned + Send + 'static,
     {
-        self.request_with_token(method_class, method, query_params, None)
-            .await
+        self.request_with_token(
+            method_class,
+            method,
+            Method::GET,
+            query_params,
+            HeaderMap::new(),
+            None,
+        )
+        .await
+    }
+
+    async fn delete_with_token<Resp>(
+        &self,
+        method_class: MethodClass,
+        method: &str,
+    ) -> Result<Resp, CloudProverError>
+    where
+        Resp: serde::de::DeserializeOwned + Send + 'static,
+    {
+        self.request_with_token(
+            method_class,
+            method,
+            Method::DELETE,
+            None,
+            HeaderMap::new(),
+            None,
+        )
+        .await
     }
 
     async fn request_with_token<Resp>(
         &self,
         method_class: MethodClass,
         method: &str,
+        http_method: Method,
+        query_params: Option<HashMap<String, String>>,
+        headers: HeaderMap,
+        request_body: Option<Vec<u8>>,
+    ) -> Result<Resp, CloudProverError>
+    where
+        Resp: serde::de::DeserializeOwned + Send + 'static,
+    {
+        self.send_with_token(
+            method_class,
+            method,
+            http_method,
+            query_params,
+            headers,
+            request_body,
+        )
+        .await
+        .map(|(resp, _, _)| resp)
+    }
+
+    // Same as `request_with_token`, also returning the HTTP status of the response, which tells
+    // apart outcomes such as 200 OK and 202 Accepted, and the time from sending the request until
+    // its response body was read, retries included.
+    async fn send_with_token<Resp>(
+        &self,
+        method_class: MethodClass,
+        method: &str,
+        http_method: Method,
         query_params: Option<HashMap<String, String>>,
-        request_body: Option<String>,
-    ) -> anyhow::Result<Resp>
+        mut headers: HeaderMap,
+        mut request_body: Option<Vec<u8>>,
+    ) -> Result<(Resp, StatusCode, Duration), CloudProverError>
     where
-        Resp: serde::de::DeserializeOwned,
+        Resp: serde::de::DeserializeOwned + Send + 'static,
     {
+        let _active = self
+            .active_requests
+            .acquire()
+            .await
+            .map_err(|_| CloudProverError::ShutDown)?;
+        if let Some(breaker) = &self.breaker {
+            breaker.lock().unwrap().check()?;
+        }
         let url = self.build_url(method_class, method, query_params)?;
+        if let Some(signer) = &self.signer {
+            // Compress the body here rather than in the middleware, so that the signature covers
+            // the bytes sent.  The middleware leaves bodies with a `Content-Encoding` alone.
+            if let (Some(body), Some((compression, min_body_size))) =
+                (&mut request_body, self.request_compression)
+            {
+                if let Some(encoding) = compression.content_encoding() {
+                    if body.len() >= min_body_size && !headers.contains_key(CONTENT_ENCODING) {
+                        *body = compress_body(compression, body)
+                            .await
+                            .map_err(|e| CloudProverError::Signing(e.into()))?;
+                        headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+                    }
+                }
+            }
+            let path_and_query = match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            };
+            let signature = signer
+                .sign(
+                    &http_method,
+                    &path_and_query,
+                    request_body.as_deref().unwrap_or_default(),
+                )
+                .await
+                .map_err(CloudProverError::Signing)?;
+            headers.extend(signature);
+        }
 
-        log::info!("[Sindri client]: {:?}", url.as_str());
+        log::info!("{}: {:?}", self.log_prefix, url.as_str());
+        trace_event!(method, http_method = %http_method, %url, "request sent");
 
         let resp_builder = match request_body {
-            Some(body) => self
-                .client
-                .post(url)
-                .header(CONTENT_TYPE, "application/json")
-                .body(body),
-            None => self.client.get(url),
+            Some(body) => {
+                log::debug!(
+                    "{}, {method}, request body: {} bytes",
+                    self.log_prefix,
+                    body.len()
+                );
+                self.client
+                    .request(http_method, url)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(body)
+            }
+            None => self.client.request(http_method, url),
         };
 
+        let timeout = self
+            .method_timeouts
+            .get(method)
+            .copied()
+            .unwrap_or(self.send_timeout);
+        let credentials = self.credentials.read().unwrap().clone();
+        let token = credentials
+            .token()
+            .await
+            .map_err(CloudProverError::Credentials)?;
+        let attempts = AttemptCounter::default();
         let resp_builder = resp_builder
-            .timeout(self.send_timeout)
-            .bearer_auth(&self.api_key);
+            .headers(headers)
+            .timeout(timeout)
+            .bearer_auth(token)
+            .with_extension(attempts.clone());
 
-        let response = resp_builder.send().await?;
+        // Held until the response body has been read.
+        let _permit = match &self.in_flight {
+            Some(in_flight) => Some(
+                in_flight
+                    .acquire()
+                    .await
+                    .expect("the in-flight semaphore is never closed"),
+            ),
+            None => None,
+        };
+        let start = Instant::now();
+        let result = resp_builder.send().await;
+        if let Some(breaker) = &self.breaker {
+            let failed = match &result {
+                Ok(response) => is_retryable_status(response.status()),
+                Err(_) => true,
+            };
+            breaker.lock().unwrap().record(!failed);
+        }
+        if let Some(rate_limit) = result
+            .as_ref()
+            .ok()
+            .and_then(|response| RateLimit::from_headers(response.headers()))
+        {
+            *self.rate_limit.lock().unwrap() = Some(rate_limit);
+        }
+        trace_event!(
+            method,
+            status = ?result.as_ref().ok().map(|response| response.status()),
+            attempts = attempts.attempts(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "response received"
+        );
+        self.metrics.record_request(
+            method,
+            result.as_ref().ok().map(|response| response.status()),
+            start.elapsed(),
+        );
+        if attempts.attempts() > 1 {
+            self.metrics.record_retries(method, attempts.attempts() - 1);
+            self.retries
+                .fetch_add(u64::from(attempts.attempts() - 1), Ordering::Relaxed);
+        }
+
+        let response = result?;
 
         let status = response.status();
         if !(status >= http::status::StatusCode::OK && status <= http::status::StatusCode::ACCEPTED)
         {
-            anyhow::bail!("[Sindri client], {method}, status not ok: {}", status)
+            let body = self
+                .read_body(method, response)
+                .await
+                .map(|body| String::from_utf8_lossy(&body).into_owned())
+                .unwrap_or_default();
+            if self.log_bodies {
+                log::debug!(
+                    "{}, {method}, status not ok: {status}, body: {}",
+                    self.log_prefix,
+                    self.body_excerpt(&body, false)
+                );
+            } else {
+                log::debug!("{}, {method}, status not ok: {status}", self.log_prefix);
+            }
+            if status == http::status::StatusCode::UNAUTHORIZED {
+                return Err(CloudProverError::Unauthorized);
+            }
+            return Err(CloudProverError::HttpStatus { status, body });
         }
 
-        let response_body = response.text().await?;
+        let resp = self.deserialize_body(method, response).await?;
+        let elapsed = start.elapsed();
+        log::info!(
+            "{}, {method}, received response after {elapsed:?}",
+            self.log_prefix
+        );
+        Ok((resp, status, elapsed))
+    }
+
+    // Deserialize the response body while it streams in, so that large proofs are never held in
+    // memory both as text and parsed.  The text is only kept when it is going to be logged.
+    async fn deserialize_body<Resp>(
+        &self,
+        method: &str,
+        response: reqwest::Response,
+    ) -> Result<Resp, CloudProverError>
+    where
+        Resp: serde::de::DeserializeOwned + Send + 'static,
+    {
+        let wire_length = response.content_length();
+        let body = DecodedBody::new(response);
+        let (wire_bytes, compressed) = (body.wire_bytes.clone(), body.compressed);
+        let reader = SyncIoBridge::new(body.reader);
+        // A character takes at most four bytes in UTF-8.
+        let capture = (self.log_bodies && log::log_enabled!(log::Level::Debug)).then(|| {
+            self.log_body_max_chars
+                .map_or(usize::MAX, |chars| chars.saturating_mul(4))
+        });
+        let mut reader = BodyReader::new(reader, self.max_response_bytes, capture);
+
+        // The JSON parser is synchronous and reading blocks on the network, so it runs on a
+        // blocking thread.
+        let (result, reader) = tokio::task::spawn_blocking(move || {
+            let result = from_reader_unbounded::<Resp, _>(&mut reader);
+            (result, reader)
+        })
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
 
-        log::info!("[Sindri client], {method}, received response");
-        log::debug!("[Sindri client], {method}, response: {response_body}");
+        log::debug!(
+            "{}, {method}, response body: {} bytes on the wire, {} bytes decompressed",
+            self.log_prefix,
+            wire_bytes.load(Ordering::Relaxed),
+            reader.read
+        );
+        if let Some(captured) = &reader.captured {
+            log::debug!(
+                "{}, {method}, response: {}",
+                self.log_prefix,
+                self.body_excerpt(
+                    &String::from_utf8_lossy(captured),
+                    reader.read > captured.len()
+                )
+            );
+        }
+        if let Some(limit) = reader.exceeded_limit {
+            return Err(CloudProverError::ResponseTooLarge { limit });
+        }
+        if let Some(e) = reader.read_error {
+            return Err(body_read_error(e));
+        }
+        // Without a content length to check against, as for compressed or chunked responses, a body
+        // that ends before the JSON value does was cut off, e.g. by a connection dropped at a zstd
+        // frame boundary.  With one, the server really sent incomplete JSON.
+        result.map_err(|e| match e.classify() {
+            serde_json::error::Category::Eof if compressed || wire_length.is_none() => {
+                CloudProverError::IncompleteBody(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    e,
+                ))
+            }
+            _ => e.into(),
+        })
+    }
 
-        // Temporary location of the solution to issues surrounding deserializing deeply nested JSON data.
-        // Mimics the upstream solution:
-        // https://github.com/scroll-tech/zkevm-circuits/blob/e19504c00b5b5b39b3de7bad0c186b4dbcc61eb5/prover/src/io.rs#L22
-        let mut deserializer = serde_json::Deserializer::from_str(&response_body);
-        deserializer.disable_recursion_limit();
-        let deserializer = serde_stacker::Deserializer::new(&mut deserializer);
+    // The start of `body` as logged, ending in an ellipsis when cut off by `log_body_max_chars` or
+    // when the body is `truncated` already.
+    fn body_excerpt<'a>(&self, body: &'a str, truncated: bool) -> std::borrow::Cow<'a, str> {
+        let cut = self
+            .log_body_max_chars
+            .and_then(|chars| body.char_indices().nth(chars))
+            .map(|(end, _)| end);
+        match cut {
+            Some(end) => format!("{}…", &body[..end]).into(),
+            None if truncated => format!("{body}…").into(),
+            None => body.into(),
+        }
+    }
 
-        serde::Deserialize::deserialize(deserializer).map_err(|e| anyhow::anyhow!(e))
+    // Read the whole (decompressed) response body, giving up once it exceeds `max_response_bytes`.
+    async fn read_body(
+        &self,
+        method: &str,
+        response: reqwest::Response,
+    ) -> Result<Vec<u8>, CloudProverError> {
+        let mut decoded = DecodedBody::new(response);
+        let mut body = Vec::new();
+        while decoded
+            .reader
+            .read_buf(&mut body)
+            .await
+            .map_err(body_read_error)?
+            > 0
+        {
+            if let Some(limit) = self.max_response_bytes {
+                if body.len() > limit {
+                    return Err(CloudProverError::ResponseTooLarge { limit });
+                }
+            }
+        }
+        log::debug!(
+            "{}, {method}, response body: {} bytes on the wire, {} bytes decompressed",
+            self.log_prefix,
+            decoded.wire_bytes(),
+            body.len()
+        );
+        Ok(body)
     }
 }
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use reqwest::{header::HeaderMap, Method};
+
+// Signs requests for gateways in front of Sindri that require e.g. an HMAC over the request on top
+// of the bearer token.  `path_and_query` is the request target as sent, such as
+// `/api/v1/proof/<id>/detail?include_proof=true`, and `body` the body as sent, i.e. after
+// `request_compression` is applied.  With a client passed to `CloudProver::with_client`, `body` is
+// the body as handed to that client, which must then not alter it.  The returned headers are added
+// to the request and sent unchanged with every retry.
+#[async_trait]
+pub trait RequestSigner: Send + Sync {
+    async fn sign(
+        &self,
+        method: &Method,
+        path_and_query: &str,
+        body: &[u8],
+    ) -> anyhow::Result<HeaderMap>;
+}
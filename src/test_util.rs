@@ -0,0 +1,194 @@
+use crate::prover::{circuit_id, CloudProverConfig, DEFAULT_CIRCUIT_NAMESPACE};
+use scroll_proving_sdk::prover::CircuitType;
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer};
+
+pub use wiremock::{Request, ResponseTemplate};
+
+// A local stand-in for the Sindri API, so that `CloudProver` can be exercised end-to-end without a
+// Sindri account.  Each `mock_*` method mounts a canned response for one route; requests to routes
+// that were not mounted are answered with 404.
+pub struct MockSindri {
+    server: MockServer,
+}
+
+impl MockSindri {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    // Point an existing config at the mock server.  Retrying is cut down to a single short wait
+    // so that tests exercising retryable failures stay fast.
+    pub fn configure(&self, mut cfg: CloudProverConfig) -> CloudProverConfig {
+        cfg.base_url = self.uri();
+        cfg.retry_count = 1;
+        cfg.retry_wait_time_sec = 1;
+        cfg
+    }
+
+    // Respond to `circuit/{id}/prove` for the given circuit.
+    pub async fn mock_prove(
+        &self,
+        circuit_type: CircuitType,
+        circuit_version: &str,
+        response: ResponseTemplate,
+    ) {
+        self.mount(
+            "POST",
+            &circuit_path(circuit_type, circuit_version, "prove"),
+            response,
+        )
+        .await;
+    }
+
+    // Respond to `circuit/{id}/detail` for the given circuit.
+    pub async fn mock_circuit_detail(
+        &self,
+        circuit_type: CircuitType,
+        circuit_version: &str,
+        response: ResponseTemplate,
+    ) {
+        self.mount(
+            "GET",
+            &circuit_path(circuit_type, circuit_version, "detail"),
+            response,
+        )
+        .await;
+    }
+
+    // Respond to `proof/{id}/detail`.
+    pub async fn mock_proof_detail(&self, proof_id: &str, response: ResponseTemplate) {
+        self.mount("GET", &format!("/api/v1/proof/{proof_id}/detail"), response)
+            .await;
+    }
+
+    // Respond to `proof/{id}/verify`.
+    pub async fn mock_proof_verify(&self, proof_id: &str, response: ResponseTemplate) {
+        self.mount("GET", &format!("/api/v1/proof/{proof_id}/verify"), response)
+            .await;
+    }
+
+    // Respond to `proof/{id}/delete`.
+    pub async fn mock_proof_delete(&self, proof_id: &str, response: ResponseTemplate) {
+        self.mount(
+            "DELETE",
+            &format!("/api/v1/proof/{proof_id}/delete"),
+            response,
+        )
+        .await;
+    }
+
+    // Respond to the given route at most `times` times, after which later mounts (or the 404
+    // fallback) take over.  Useful for simulating a transient failure followed by success.
+    pub async fn mock_times(
+        &self,
+        http_method: &str,
+        route: &str,
+        times: u64,
+        response: ResponseTemplate,
+    ) {
+        Mock::given(method(http_method))
+            .and(path(route))
+            .respond_with(response)
+            .up_to_n_times(times)
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    // Respond to the given route, expecting it to be requested exactly `times` times by the time
+    // the mock server is dropped, which panics otherwise.
+    pub async fn mock_expect(
+        &self,
+        http_method: &str,
+        route: &str,
+        times: u64,
+        response: ResponseTemplate,
+    ) {
+        Mock::given(method(http_method))
+            .and(path(route))
+            .respond_with(response)
+            .expect(times)
+            .mount(&self.server)
+            .await;
+    }
+
+    // Number of requests the mock server has received so far.
+    pub async fn received_requests(&self) -> usize {
+        self.requests().await.len()
+    }
+
+    // Requests the mock server has received so far, oldest first.
+    pub async fn requests(&self) -> Vec<Request> {
+        self.server.received_requests().await.unwrap_or_default()
+    }
+
+    async fn mount(&self, http_method: &str, route: &str, response: ResponseTemplate) {
+        Mock::given(method(http_method))
+            .and(path(route))
+            .respond_with(response)
+            .mount(&self.server)
+            .await;
+    }
+}
+
+// Path of a circuit-scoped route, as requested by `CloudProver` with the default namespace.
+pub fn circuit_path(circuit_type: CircuitType, circuit_version: &str, method: &str) -> String {
+    let id = circuit_id(DEFAULT_CIRCUIT_NAMESPACE, circuit_type, circuit_version)
+        .expect("circuit type must be defined");
+    format!("/api/v1/circuit/{id}/{method}")
+}
+
+// Canned body of a Sindri proof detail response with the given id and status, e.g. "Queued",
+// "In Progress", "Ready" or "Failed".  Ready proofs carry a proof and public inputs.
+pub fn proof_info(proof_id: &str, status: &str) -> Value {
+    let ready = status == "Ready";
+    json!({
+        "circuit_name": "scroll-tech/chunk_prover:v0.13.1",
+        "compute_time_sec": ready.then_some(12.5),
+        "date_created": "2024-10-30T12:34:56.789Z",
+        "error": (status == "Failed").then_some("proving failed"),
+        "proof_id": proof_id,
+        "proof": ready.then(|| json!({ "proof": "AAAA" })),
+        "public": ready.then(|| json!({ "instances": ["0x01"] })),
+        "queue_time_sec": (status != "Queued").then_some(1.5),
+        "status": status,
+        "verification_key": null,
+    })
+}
+
+// Canned body of a Sindri circuit detail response carrying the given verification key, which
+// Sindri encodes as unpadded URL-safe base64.
+pub fn circuit_info(verification_key: &str) -> Value {
+    json!({
+        "circuit_id": "00000000-0000-0000-0000-000000000000",
+        "circuit_name": "chunk_prover",
+        "circuit_type": "halo2",
+        "date_created": "2024-10-30T12:34:56.789Z",
+        "error": null,
+        "num_proofs": 0,
+        "proving_scheme": "shplonk",
+        "status": "Ready",
+        "verification_key": { "verification_key": verification_key },
+    })
+}
+
+// Canned body of a Sindri proof verification response.
+pub fn verify_info(success: bool) -> Value {
+    json!({
+        "success": success,
+        "message": (!success).then_some("proof is invalid"),
+    })
+}
+
+// Successful JSON response carrying `body`.
+pub fn json_response(body: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(body)
+}
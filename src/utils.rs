@@ -1,31 +1,66 @@
+use crate::error::CloudProverError;
 use crate::prover::SindriProofInfoResponse;
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 
-// Convert an ISO 8601 string to a f64 timestamp.
-fn iso8601_to_f64(iso8601_str: &str) -> Result<f64, chrono::ParseError> {
-    // Parse the ISO 8601 string into a DateTime<FixedOffset> object
-    let datetime: DateTime<FixedOffset> = iso8601_str.parse()?;
+// Parse a Sindri timestamp, given either as an RFC 3339 string or as seconds since the Unix epoch.
+pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, CloudProverError> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite())
+        // Round to microseconds, the finest precision Sindri reports, to absorb float error.  The
+        // whole value is rounded, so that a fraction rounding up carries into the seconds.
+        .and_then(|secs| DateTime::from_timestamp_micros((secs * 1_000_000.0).round() as i64))
+        .ok_or_else(|| CloudProverError::InvalidTimestamp(value.to_string()))
+}
 
-    // Convert the DateTime<FixedOffset> object to a timestamp (seconds since the Unix epoch)
+// Convert a timestamp to fractional seconds since the Unix epoch.
+fn timestamp_to_f64(datetime: DateTime<Utc>) -> f64 {
     let timestamp = datetime.timestamp() as f64;
 
     // Convert the nanoseconds part to f64 and add it to the timestamp
     let nanoseconds = datetime.timestamp_subsec_nanos() as f64;
-    let timestamp_f64 = timestamp + nanoseconds / 1_000_000_000.0;
+    timestamp + nanoseconds / 1_000_000_000.0
+}
 
-    Ok(timestamp_f64)
+// Timing of a proof task.  Timestamps are seconds since the Unix epoch.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProvingStats {
+    pub created_at: f64,
+    pub started_at: Option<f64>,
+    pub finished_at: Option<f64>,
+    pub queue_time_sec: Option<f64>,
+    pub compute_time_sec: Option<f64>,
 }
 
-// Return the created, start, and finish times of the proof task.
+// Return the created, start, and finish times of the proof task, along with its durations.
+// `log_prefix` labels the warning logged when the task has no usable creation time.
 pub fn proving_timestamps_from_response(
+    log_prefix: &str,
     resp: &SindriProofInfoResponse,
-) -> (f64, Option<f64>, Option<f64>) {
+) -> ProvingStats {
     let mut started_at: Option<f64> = None;
     let mut finished_at: Option<f64> = None;
 
-    let created_at: f64 = match iso8601_to_f64(&resp.date_created) {
-        Ok(created_at) => created_at,
-        Err(_) => return (0.0, None, None),
+    let created_at: f64 = match resp.created_at() {
+        Ok(created_at) => timestamp_to_f64(created_at),
+        Err(e) => {
+            log::warn!(
+                "{log_prefix}, proof {} has no usable creation time: {}",
+                resp.proof_id,
+                e
+            );
+            return ProvingStats {
+                queue_time_sec: resp.queue_time_sec,
+                compute_time_sec: resp.compute_time_sec,
+                ..Default::default()
+            };
+        }
     };
     let compute_time_sec = resp.compute_time_sec.unwrap_or(0.0);
     let queue_time_sec = resp.queue_time_sec.unwrap_or(0.0);
@@ -44,5 +79,68 @@ pub fn proving_timestamps_from_response(
         started_at,
         finished_at
     );
-    (created_at, started_at, finished_at)
+    ProvingStats {
+        created_at,
+        started_at,
+        finished_at,
+        queue_time_sec: resp.queue_time_sec,
+        compute_time_sec: resp.compute_time_sec,
+    }
+}
+
+// Deserialize JSON without serde_json's recursion limit; proofs and proof inputs nest deeply.
+pub fn from_str_unbounded<T: DeserializeOwned>(json: &str) -> serde_json::Result<T> {
+    // Temporary location of the solution to issues surrounding deserializing deeply nested JSON data.
+    // Mimics the upstream solution:
+    // https://github.com/scroll-tech/zkevm-circuits/blob/e19504c00b5b5b39b3de7bad0c186b4dbcc61eb5/prover/src/io.rs#L22
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    deserializer.disable_recursion_limit();
+    let deserializer = serde_stacker::Deserializer::new(&mut deserializer);
+
+    T::deserialize(deserializer)
+}
+
+// Same as `from_str_unbounded`, reading incrementally so that the JSON text is never held in
+// memory as a whole.
+pub fn from_reader_unbounded<T: DeserializeOwned, R: std::io::Read>(
+    reader: R,
+) -> serde_json::Result<T> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    deserializer.disable_recursion_limit();
+    let deserializer = serde_stacker::Deserializer::new(&mut deserializer);
+
+    T::deserialize(deserializer)
+}
+
+// Replace each `${NAME}` in `value` with the value of the environment variable `NAME`.  Any other
+// `$` is kept as is.  Undefined variables and malformed references are errors, which name the
+// variable but never the value, as it may hold secrets.
+pub fn expand_env_vars(value: &str) -> Result<String, CloudProverError> {
+    let invalid = |msg: String| CloudProverError::InvalidConfig(msg);
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let end = reference
+            .find('}')
+            .ok_or_else(|| invalid("unterminated `${` in a config value".to_string()))?;
+        let name = &reference[..end];
+        let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(invalid(format!(
+                "invalid environment variable name {name:?}"
+            )));
+        }
+        let var = std::env::var(name).map_err(|_| {
+            invalid(format!(
+                "environment variable `{name}` is undefined or not valid UTF-8"
+            ))
+        })?;
+        expanded.push_str(&var);
+        rest = &reference[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
 }
@@ -0,0 +1,95 @@
+use crate::error::CloudProverError;
+use crate::utils::from_str_unbounded;
+#[cfg(feature = "local-verify")]
+use prover_darwin_v2::{common::Verifier, BatchProof, ChunkProof, CompressionCircuit, Snark};
+use scroll_proving_sdk::prover::CircuitType;
+use serde::Deserialize;
+
+// The parts of a chunk or batch proof checked before running the verifier, as Scroll's prover
+// serializes them: the vk and the instances in standard base64, the instances as 32-byte
+// big-endian field elements.
+#[derive(Deserialize)]
+struct ProofParts {
+    vk: String,
+    instances: String,
+}
+
+// Check a chunk or batch proof returned by `query_task` against the circuit's verification key,
+// in Scroll's standard base64 as returned by `get_vks` or `reformat_vk`, and against the public
+// inputs Sindri reported for the task, see `TaskMetadata::public`, whose `instances` must then
+// match the proof's.  This does not verify the proof itself, see `verify_locally`.
+//
+// Returns `Ok(false)` if the proof was made for another vk or does not match `public`.  Bundle
+// proofs are checked by Scroll's on-chain verifier contract instead, and are not supported here.
+pub fn check_proof(
+    circuit_type: CircuitType,
+    proof: &str,
+    vk: &str,
+    public: Option<&serde_json::Value>,
+) -> Result<bool, CloudProverError> {
+    match circuit_type {
+        CircuitType::Chunk | CircuitType::Batch => {}
+        CircuitType::Bundle => {
+            return Err(CloudProverError::LocalVerification(
+                "bundle proofs are verified by the on-chain verifier contract".to_string(),
+            ))
+        }
+        CircuitType::Undefined => return Err(CloudProverError::UndefinedCircuitType),
+    }
+    let raw_vk = base64::decode_config(vk, base64::STANDARD)?;
+    let parts: ProofParts = from_str_unbounded(proof)?;
+    if base64::decode_config(parts.vk, base64::STANDARD)? != raw_vk {
+        return Ok(false);
+    }
+    match public {
+        Some(public) => {
+            let instances = base64::decode_config(parts.instances, base64::STANDARD)?;
+            instances_match(public, &instances)
+        }
+        None => Ok(true),
+    }
+}
+
+// `check_proof`, then Scroll's own verification of the proof with `verifier`, built by the caller
+// from the KZG params of the circuit's last compression layer and the same `vk`, e.g. with
+// `Verifier::from_params`.  Like Scroll's verifier, panics on a proof it cannot read.
+#[cfg(feature = "local-verify")]
+pub fn verify_locally(
+    circuit_type: CircuitType,
+    verifier: &Verifier<'_, CompressionCircuit>,
+    proof: &str,
+    vk: &str,
+    public: Option<&serde_json::Value>,
+) -> Result<bool, CloudProverError> {
+    if !check_proof(circuit_type, proof, vk, public)? {
+        return Ok(false);
+    }
+    let snark = match circuit_type {
+        CircuitType::Chunk => from_str_unbounded::<ChunkProof>(proof)?.to_snark(),
+        _ => Snark::from(&from_str_unbounded::<BatchProof>(proof)?),
+    };
+    Ok(verifier.verify_snark(snark))
+}
+
+// Whether the hex-encoded `instances` of Sindri's public inputs, e.g. `{"instances": ["0x01"]}`,
+// are the field elements serialized in `instances`.
+fn instances_match(public: &serde_json::Value, instances: &[u8]) -> Result<bool, CloudProverError> {
+    let unreadable =
+        || CloudProverError::LocalVerification(format!("unrecognized public inputs: {public}"));
+    let values = public["instances"].as_array().ok_or_else(unreadable)?;
+    if values.len() * 32 != instances.len() {
+        return Ok(false);
+    }
+    for (value, expected) in values.iter().zip(instances.chunks(32)) {
+        let digits = value
+            .as_str()
+            .map(|value| value.trim_start_matches("0x"))
+            .ok_or_else(unreadable)?;
+        let digits = format!("{digits:0>64}");
+        let word = hex::decode(&digits).map_err(|_| unreadable())?;
+        if word != expected {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
@@ -0,0 +1,90 @@
+use reqwest::StatusCode;
+use sindri_scroll_sdk::error::{is_retryable_status, CloudProverError};
+
+// Verify that rate limiting and server errors are classified as retryable.
+#[test]
+fn test_retryable_statuses() {
+    for status in [
+        StatusCode::TOO_MANY_REQUESTS,
+        StatusCode::REQUEST_TIMEOUT,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        StatusCode::BAD_GATEWAY,
+        StatusCode::SERVICE_UNAVAILABLE,
+    ] {
+        assert!(is_retryable_status(status), "{status} should be retryable");
+    }
+}
+
+// Verify that client errors other than 408/429 fail fast.
+#[test]
+fn test_terminal_statuses() {
+    for status in [
+        StatusCode::BAD_REQUEST,
+        StatusCode::UNAUTHORIZED,
+        StatusCode::FORBIDDEN,
+        StatusCode::NOT_FOUND,
+    ] {
+        assert!(!is_retryable_status(status), "{status} should be terminal");
+    }
+}
+
+// Verify that `is_retryable` follows the status classification and never retries
+// version mismatches.
+#[test]
+fn test_error_is_retryable() {
+    let rate_limited = CloudProverError::HttpStatus {
+        status: StatusCode::TOO_MANY_REQUESTS,
+        body: String::new(),
+    };
+    let not_found = CloudProverError::HttpStatus {
+        status: StatusCode::NOT_FOUND,
+        body: String::new(),
+    };
+    let mismatch = CloudProverError::VersionMismatch {
+        supported: vec!["v0.13.1".to_string()],
+        actual: "v0.12.0".to_string(),
+    };
+
+    assert!(rate_limited.is_retryable());
+    assert!(!not_found.is_retryable());
+    assert!(!mismatch.is_retryable());
+}
+
+// Verify that the body of an error response is shown in the error message, truncated if long.
+#[test]
+fn test_http_status_display_includes_body() {
+    let error = CloudProverError::HttpStatus {
+        status: StatusCode::BAD_REQUEST,
+        body: r#"{"detail": "proof_input is malformed"}"#.to_string(),
+    };
+    let message = error.to_string();
+    assert!(message.contains("400 Bad Request"));
+    assert!(message.contains("proof_input is malformed"));
+
+    let body = "x".repeat(5000);
+    let error = CloudProverError::HttpStatus {
+        status: StatusCode::BAD_REQUEST,
+        body: body.clone(),
+    };
+    let message = error.to_string();
+    assert!(message.len() < 1200);
+    assert!(message.contains("(5000 bytes total)"));
+    assert!(matches!(error, CloudProverError::HttpStatus { body: full, .. } if full == body));
+}
+
+// Verify that error messages are plain, leaving the client prefix to the logs and the
+// `ProvingService` boundary.
+#[test]
+fn test_error_display_plain() {
+    for error in [
+        CloudProverError::VersionMismatch {
+            supported: vec!["v0.13.1".to_string()],
+            actual: "v0.12.0".to_string(),
+        },
+        CloudProverError::UndefinedCircuitType,
+        CloudProverError::InvalidCircuitSlug("chunk".to_string()),
+        CloudProverError::InvalidConfig("retry_count is too large".to_string()),
+    ] {
+        assert!(!error.to_string().contains("[Sindri client"), "{error}");
+    }
+}
@@ -1,5 +1,9 @@
 use scroll_proving_sdk::prover::CircuitType;
-use sindri_scroll_sdk::prover::{CloudProver, CloudProverConfig};
+use sindri_scroll_sdk::error::CloudProverError;
+use sindri_scroll_sdk::prover::{
+    CloudProver, CloudProverConfig, RetryJitter, DEFAULT_CONNECTION_TIMEOUT_SEC,
+    DEFAULT_RETRY_COUNT, DEFAULT_RETRY_WAIT_TIME_SEC,
+};
 
 // Ensures that configuration file loading does not require environment variables
 #[test]
@@ -11,6 +15,7 @@ fn test_config_without_envs() {
         [
             ("N_WORKERS", None::<String>),
             ("COORDINATOR_BASE_URL", None),
+            ("PROVING_SERVICE_CIRCUIT_VERSION", None),
         ],
         || {
             let default_config_path = "tests/test_data/default_config.json";
@@ -24,6 +29,15 @@ fn test_config_without_envs() {
             assert_eq!(cfg.sdk_config.prover.circuit_types[2], CircuitType::Bundle);
             assert_eq!(cfg.sdk_config.db_path, Some("db".to_string()));
             assert_eq!(cfg.sdk_config.prover.n_workers, 1_usize);
+
+            // The test_data file omits `circuit_version`, so the default applies
+            assert_eq!(cfg.circuit_version, "v0.13.1");
+            // Sindri-side verification stays on unless explicitly disabled
+            assert!(cfg.perform_verify);
+            assert!(cfg.strict_version_check);
+            assert_eq!(cfg.connect_timeout_sec, 10);
+            assert_eq!(cfg.pool_max_idle_per_host, None);
+            assert_eq!(cfg.pool_idle_timeout_sec, None);
         },
     );
 }
@@ -37,6 +51,8 @@ fn test_config_with_envs() {
     let num_workers_override = "10";
     let coordinator_url_env_name = "COORDINATOR_BASE_URL";
     let coordinator_url_override = "my-special-coordinator";
+    let circuit_version_env_name = "PROVING_SERVICE_CIRCUIT_VERSION";
+    let circuit_version_override = "v0.13.2";
 
     // The utility function below sets environment variables for the duration of the closure.
     // A singleton mutex ensures that the other test, `test_config_without_envs`, is not
@@ -45,6 +61,7 @@ fn test_config_with_envs() {
         [
             (num_workers_env_name, Some(num_workers_override)),
             (coordinator_url_env_name, Some(coordinator_url_override)),
+            (circuit_version_env_name, Some(circuit_version_override)),
         ],
         || {
             let default_config_path = "tests/test_data/default_config.json";
@@ -63,6 +80,10 @@ fn test_config_with_envs() {
                 cfg.sdk_config.coordinator.base_url, coordinator_url_override,
                 "Coordinator base url override was not successful"
             );
+            assert_eq!(
+                cfg.circuit_version, circuit_version_override,
+                "Circuit version override was not successful"
+            );
         },
     );
 }
@@ -79,3 +100,313 @@ async fn test_initialize_from_config_pipeline() {
     // Ensure that a cloud prover may be built from the config file
     let _cloud_prover = CloudProver::new(cfg);
 }
+
+// Ensures that a malformed base url is reported as an error instead of panicking
+#[test]
+fn test_try_new_invalid_base_url() {
+    let default_config_path = "tests/test_data/default_config.json";
+    let mut cfg = CloudProverConfig::from_file_and_env(default_config_path.to_string())
+        .expect("Issue loading test configuration file");
+    cfg.base_url = "not a url".to_string();
+
+    assert!(matches!(
+        CloudProver::try_new(cfg),
+        Err(CloudProverError::Url(_))
+    ));
+}
+
+// Ensures that TOML and YAML config files are parsed into the same values as the JSON file
+#[test]
+fn test_config_from_toml_and_yaml() {
+    let json_cfg = CloudProverConfig::from_file("tests/test_data/default_config.json".to_string())
+        .expect("Issue loading JSON configuration file");
+
+    for path in [
+        "tests/test_data/default_config.toml",
+        "tests/test_data/default_config.yaml",
+    ] {
+        let cfg = CloudProverConfig::from_file(path.to_string())
+            .unwrap_or_else(|e| panic!("Issue loading {path}: {e}"));
+
+        assert_eq!(cfg.base_url, json_cfg.base_url);
+        assert_eq!(cfg.api_key, json_cfg.api_key);
+        assert_eq!(cfg.retry_count, json_cfg.retry_count);
+        assert_eq!(cfg.connection_timeout_sec, json_cfg.connection_timeout_sec);
+        assert_eq!(
+            cfg.sdk_config.prover.circuit_types,
+            json_cfg.sdk_config.prover.circuit_types
+        );
+        assert_eq!(
+            cfg.sdk_config.coordinator.base_url,
+            json_cfg.sdk_config.coordinator.base_url
+        );
+        assert_eq!(cfg.sdk_config.db_path, json_cfg.sdk_config.db_path);
+    }
+}
+
+// Ensures that the API key never appears in the config's debug output
+#[test]
+fn test_config_debug_redacts_api_key() {
+    let default_config_path = "tests/test_data/default_config.json";
+    let mut cfg = CloudProverConfig::from_file(default_config_path.to_string())
+        .expect("Issue loading test configuration file");
+    cfg.api_key = "super-secret-sindri-key".to_string();
+
+    let debug = format!("{cfg:?}");
+    assert!(!debug.contains("super-secret-sindri-key"));
+    assert!(debug.contains("api_key: \"***\""));
+    assert!(debug.contains("https://sindri.app"));
+}
+
+// Ensures that the retry jitter settings are parsed and accepted when building the prover
+#[test]
+fn test_config_retry_jitter() {
+    let contents = std::fs::read_to_string("tests/test_data/default_config.json").unwrap();
+    let mut value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    value["retry_jitter"] = "equal".into();
+    value["max_retry_interval_sec"] = 60.into();
+
+    let cfg = CloudProverConfig::from_reader(value.to_string().as_bytes())
+        .expect("Issue parsing configuration");
+    assert_eq!(cfg.retry_jitter, RetryJitter::Equal);
+    assert_eq!(cfg.max_retry_interval_sec, Some(60));
+    assert!(CloudProver::try_new(cfg).is_ok());
+
+    let cfg = CloudProverConfig::from_file("tests/test_data/default_config.json".to_string())
+        .expect("Issue loading test configuration file");
+    assert_eq!(cfg.retry_jitter, RetryJitter::Full);
+    assert_eq!(cfg.max_retry_interval_sec, None);
+}
+
+// Ensures that degenerate timeout and retry settings are rejected when building the prover
+#[test]
+fn test_config_validate() {
+    let default_config_path = "tests/test_data/default_config.json";
+    let cfg = CloudProverConfig::from_file(default_config_path.to_string())
+        .expect("Issue loading test configuration file");
+    assert!(cfg.validate().is_ok());
+
+    let mut zero_timeout = cfg.clone();
+    zero_timeout.connection_timeout_sec = 0;
+    let mut zero_method_timeout = cfg.clone();
+    zero_method_timeout
+        .per_method_timeout_sec
+        .insert("prove".to_string(), 0);
+    let mut zero_retry_wait = cfg.clone();
+    zero_retry_wait.retry_wait_time_sec = 0;
+    for invalid in [zero_timeout, zero_method_timeout, zero_retry_wait] {
+        assert!(matches!(
+            CloudProver::try_new(invalid),
+            Err(CloudProverError::InvalidConfig(_))
+        ));
+    }
+
+    // Without retries the wait time is irrelevant.
+    let mut no_retries = cfg;
+    no_retries.retry_count = 0;
+    no_retries.retry_wait_time_sec = 0;
+    assert!(no_retries.validate().is_ok());
+}
+
+// Ensures that a client certificate for mutual TLS is loaded, and that incomplete or unreadable
+// certificate settings are rejected
+#[test]
+fn test_config_client_certificate() {
+    let default_config_path = "tests/test_data/default_config.json";
+    let cfg = CloudProverConfig::from_file(default_config_path.to_string())
+        .expect("Issue loading test configuration file");
+
+    let mut with_cert = cfg.clone();
+    with_cert.client_cert_path = Some("tests/test_data/client_cert.pem".to_string());
+    with_cert.client_key_path = Some("tests/test_data/client_key.pem".to_string());
+    assert!(CloudProver::try_new(with_cert.clone()).is_ok());
+
+    let mut cert_only = with_cert.clone();
+    cert_only.client_key_path = None;
+    let mut missing_file = with_cert.clone();
+    missing_file.client_key_path = Some("tests/test_data/missing.pem".to_string());
+    let mut swapped = with_cert;
+    swapped.client_cert_path = Some("tests/test_data/client_key.pem".to_string());
+    swapped.client_key_path = Some("tests/test_data/client_cert.pem".to_string());
+    for invalid in [cert_only, missing_file, swapped] {
+        assert!(matches!(
+            CloudProver::try_new(invalid),
+            Err(CloudProverError::InvalidConfig(_))
+        ));
+    }
+}
+
+// Ensures that a config built in code gets the same defaults as one loaded from a file, and that
+// required fields are enforced
+#[test]
+fn test_config_builder() {
+    let file_cfg = CloudProverConfig::from_file("tests/test_data/default_config.json".to_string())
+        .expect("Issue loading test configuration file");
+
+    let cfg = CloudProverConfig::builder()
+        .sdk_config(file_cfg.sdk_config.clone())
+        .base_url("https://sindri.app")
+        .api_key("secret")
+        .perform_verify(false)
+        .vk_cache_ttl_sec(600)
+        .build()
+        .unwrap();
+    assert_eq!(cfg.base_url, "https://sindri.app");
+    assert_eq!(cfg.api_key, "secret");
+    assert!(!cfg.perform_verify);
+    assert_eq!(cfg.vk_cache_ttl_sec, Some(600));
+    assert_eq!(cfg.retry_count, DEFAULT_RETRY_COUNT);
+    assert_eq!(cfg.retry_wait_time_sec, DEFAULT_RETRY_WAIT_TIME_SEC);
+    assert_eq!(cfg.connection_timeout_sec, DEFAULT_CONNECTION_TIMEOUT_SEC);
+    assert_eq!(cfg.connect_timeout_sec, file_cfg.connect_timeout_sec);
+    assert_eq!(cfg.circuit_version, file_cfg.circuit_version);
+    assert_eq!(cfg.api_path, file_cfg.api_path);
+    assert!(CloudProver::try_new(cfg).is_ok());
+
+    let missing_base_url = CloudProverConfig::builder()
+        .sdk_config(file_cfg.sdk_config.clone())
+        .api_key("secret")
+        .build();
+    assert!(matches!(
+        missing_base_url,
+        Err(CloudProverError::InvalidConfig(msg)) if msg.contains("base_url")
+    ));
+
+    let zero_timeout = CloudProverConfig::builder()
+        .sdk_config(file_cfg.sdk_config)
+        .base_url("https://sindri.app")
+        .api_key("secret")
+        .connection_timeout_sec(0)
+        .build();
+    assert!(matches!(
+        zero_timeout,
+        Err(CloudProverError::InvalidConfig(_))
+    ));
+}
+
+// Ensures that body logging is capped by default and that the cap can be lifted or logging disabled
+#[test]
+fn test_config_log_bodies() {
+    let cfg = CloudProverConfig::from_file("tests/test_data/default_config.json".to_string())
+        .expect("Issue loading test configuration file");
+    assert!(cfg.log_bodies);
+    assert_eq!(cfg.log_body_max_chars, Some(2000));
+
+    let contents = std::fs::read_to_string("tests/test_data/default_config.json").unwrap();
+    let mut value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    value["log_bodies"] = false.into();
+    value["log_body_max_chars"] = serde_json::Value::Null;
+    let cfg = CloudProverConfig::from_reader(value.to_string().as_bytes())
+        .expect("Issue parsing configuration");
+    assert!(!cfg.log_bodies);
+    assert_eq!(cfg.log_body_max_chars, None);
+    assert!(CloudProver::try_new(cfg).is_ok());
+}
+
+// Ensures that log lines are unlabeled by default and that a label is read from the config
+#[test]
+fn test_config_log_label() {
+    let cfg = CloudProverConfig::from_file("tests/test_data/default_config.json".to_string())
+        .expect("Issue loading test configuration file");
+    assert_eq!(cfg.log_label, None);
+
+    let contents = std::fs::read_to_string("tests/test_data/default_config.json").unwrap();
+    let mut value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    value["log_label"] = "batch-v0.13.1".into();
+    let cfg = CloudProverConfig::from_reader(value.to_string().as_bytes())
+        .expect("Issue parsing configuration");
+    assert_eq!(cfg.log_label.as_deref(), Some("batch-v0.13.1"));
+    assert!(format!("{cfg:?}").contains("batch-v0.13.1"));
+    assert!(CloudProver::try_new(cfg).is_ok());
+}
+
+// Ensures that a config wrapping an existing SDK config takes the builder defaults
+#[test]
+fn test_config_from_sdk_config() {
+    let file_cfg = CloudProverConfig::from_file("tests/test_data/default_config.json".to_string())
+        .expect("Issue loading test configuration file");
+
+    let cfg = CloudProverConfig::from_sdk_config(
+        file_cfg.sdk_config.clone(),
+        "https://sindri.app",
+        "secret",
+    )
+    .unwrap();
+    assert_eq!(
+        cfg.sdk_config.prover_name_prefix,
+        file_cfg.sdk_config.prover_name_prefix
+    );
+    assert_eq!(cfg.base_url, "https://sindri.app");
+    assert_eq!(cfg.api_key, "secret");
+    assert_eq!(cfg.retry_count, DEFAULT_RETRY_COUNT);
+    assert_eq!(cfg.retry_wait_time_sec, DEFAULT_RETRY_WAIT_TIME_SEC);
+    assert_eq!(cfg.connection_timeout_sec, DEFAULT_CONNECTION_TIMEOUT_SEC);
+    assert!(cfg.perform_verify);
+    assert!(CloudProver::try_new(cfg).is_ok());
+}
+
+// Ensures that `${VAR}` references in config strings, including the SDK config, are expanded
+#[test]
+fn test_config_expand_env_vars() {
+    let contents = std::fs::read_to_string("tests/test_data/default_config.json").unwrap();
+    let mut value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    value["api_key"] = "${SINDRI_TEST_API_KEY}".into();
+    value["extra_headers"] = serde_json::json!({ "x-tenant": "tenant-${SINDRI_TEST_TENANT}" });
+    value["sdk_config"]["prover_name_prefix"] = "${SINDRI_TEST_TENANT}_".into();
+    let cfg = CloudProverConfig::from_reader(value.to_string().as_bytes())
+        .expect("Issue parsing configuration");
+
+    temp_env::with_vars(
+        [
+            ("SINDRI_TEST_API_KEY", Some("secret")),
+            ("SINDRI_TEST_TENANT", Some("acme")),
+        ],
+        || {
+            let cfg = cfg.clone().expand_env_vars().unwrap();
+            assert_eq!(cfg.api_key, "secret");
+            assert_eq!(cfg.extra_headers["x-tenant"], "tenant-acme");
+            assert_eq!(cfg.sdk_config.prover_name_prefix, "acme_");
+        },
+    );
+
+    temp_env::with_var_unset("SINDRI_TEST_API_KEY", || {
+        assert!(matches!(
+            cfg.clone().expand_env_vars(),
+            Err(CloudProverError::InvalidConfig(msg)) if msg.contains("SINDRI_TEST_API_KEY")
+        ));
+    });
+}
+
+// Ensures that the API key is read from `api_key_file` without its trailing newline, and that the
+// environment override still takes precedence
+#[test]
+fn test_config_api_key_file() {
+    let dir = std::env::temp_dir().join(format!("sindri-api-key-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let key_path = dir.join("api_key");
+    std::fs::write(&key_path, "file-secret\n").unwrap();
+    let contents = std::fs::read_to_string("tests/test_data/default_config.json").unwrap();
+    let mut value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    value.as_object_mut().unwrap().remove("api_key");
+    value["api_key_file"] = key_path.to_str().unwrap().into();
+    let config_path = dir.join("config.json");
+    std::fs::write(&config_path, value.to_string()).unwrap();
+    let config_path = config_path.to_str().unwrap().to_string();
+
+    let cfg = CloudProverConfig::from_file(config_path.clone()).unwrap();
+    assert_eq!(cfg.api_key, "file-secret");
+
+    temp_env::with_var("PROVING_SERVICE_API_KEY", Some("env-secret"), || {
+        let cfg = CloudProverConfig::from_file_and_env(config_path.clone()).unwrap();
+        assert_eq!(cfg.api_key, "env-secret");
+    });
+
+    value["api_key_file"] = dir.join("missing").to_str().unwrap().into();
+    assert!(matches!(
+        CloudProverConfig::from_reader(value.to_string().as_bytes())
+            .unwrap()
+            .load_api_key_file(),
+        Err(CloudProverError::InvalidConfig(msg)) if msg.contains("api_key_file")
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,52 @@
+use scroll_proving_sdk::prover::CircuitType;
+use sindri_scroll_sdk::input::ProveInput;
+use sindri_scroll_sdk::prover::{reprocess_prove_input, validate_prove_input};
+
+// Verify that a chunk input is the array of block traces, submitted unchanged.
+#[test]
+fn test_prove_input_chunk() {
+    let traces = [serde_json::json!({ "header": { "number": "0x1" } })];
+    let input = ProveInput::chunk(&traces).unwrap();
+    assert_eq!(input.input(), r#"[{"header":{"number":"0x1"}}]"#);
+    assert_eq!(input.payload(), input.input());
+
+    let req = input.into_request("v0.13.1", "darwinV2");
+    assert_eq!(req.circuit_type, CircuitType::Chunk);
+    assert_eq!(req.circuit_version, "v0.13.1");
+    validate_prove_input(req.circuit_type, &req.input).unwrap();
+
+    assert!(ProveInput::chunk::<serde_json::Value>(&[]).is_err());
+}
+
+// Verify that a batch input passes validation, and that one of the wrong shape is rejected.
+#[test]
+fn test_prove_input_batch() {
+    let task = serde_json::json!({ "chunk_infos": [], "chunk_proofs": [], "batch_header": {} });
+    let input = ProveInput::batch(&task).unwrap();
+    assert_eq!(input.circuit_type(), CircuitType::Batch);
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(input.input()).unwrap(),
+        task
+    );
+    assert_eq!(input.payload(), input.input());
+
+    assert!(ProveInput::batch(&serde_json::json!({ "chunk_proofs": [] })).is_err());
+}
+
+// Verify that a bundle input keeps the coordinator's shape while its payload is the unwrapped
+// batch proofs, as `prove` would submit them.
+#[test]
+fn test_prove_input_bundle() {
+    let task = serde_json::from_str(r#"{"batch_proofs": []}"#).unwrap();
+    let input = ProveInput::bundle(&task).unwrap();
+    assert_eq!(input.input(), r#"{"batch_proofs":[]}"#);
+    assert_eq!(input.payload(), "[]");
+    assert_eq!(
+        reprocess_prove_input(CircuitType::Bundle, input.input()).unwrap(),
+        input.payload()
+    );
+
+    let req = input.into_request("v0.13.1", "darwinV2");
+    assert_eq!(req.circuit_type, CircuitType::Bundle);
+    validate_prove_input(req.circuit_type, &req.input).unwrap();
+}
@@ -0,0 +1,71 @@
+use sindri_scroll_sdk::middleware::parse_retry_after;
+#[cfg(feature = "zstd")]
+use sindri_scroll_sdk::middleware::ZstdRequestCompressionMiddleware;
+#[cfg(feature = "zstd")]
+use sindri_scroll_sdk::test_util::MockSindri;
+use std::time::{Duration, SystemTime};
+
+// Verify that a delay-seconds `Retry-After` value is parsed as-is.
+#[test]
+fn test_parse_retry_after_seconds() {
+    assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+    assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+}
+
+// Verify that an HTTP-date `Retry-After` value is converted into the remaining delay.
+#[test]
+fn test_parse_retry_after_http_date() {
+    let retry_at = SystemTime::now() + Duration::from_secs(60);
+    let delay =
+        parse_retry_after(&httpdate::fmt_http_date(retry_at)).expect("Failed to parse HTTP-date");
+
+    // HTTP-dates only have second precision.
+    assert!(delay <= Duration::from_secs(60));
+    assert!(delay >= Duration::from_secs(58));
+
+    // A date in the past means the client may retry immediately.
+    assert_eq!(
+        parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+        Some(Duration::ZERO)
+    );
+}
+
+// Verify that malformed `Retry-After` values are ignored.
+#[test]
+fn test_parse_retry_after_invalid() {
+    assert_eq!(parse_retry_after("soon"), None);
+    assert_eq!(parse_retry_after("-5"), None);
+}
+
+// Verify that bodies below the threshold pass through untouched while larger ones are compressed.
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn test_zstd_compression_min_body_size() {
+    let mock = MockSindri::start().await;
+    let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(ZstdRequestCompressionMiddleware::new(64))
+        .build();
+
+    let small = b"0123456789".to_vec();
+    let large = vec![b'x'; 1000];
+    for body in [&small, &large] {
+        client
+            .post(mock.uri())
+            .body(body.clone())
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let requests = mock.requests().await;
+    assert!(requests[0].headers.get("content-encoding").is_none());
+    assert_eq!(requests[0].body, small);
+    assert_eq!(requests[1].headers.get("content-encoding").unwrap(), "zstd");
+    assert!(zstd_magic(&requests[1].body));
+    assert!(requests[1].body.len() < large.len());
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_magic(body: &[u8]) -> bool {
+    body.starts_with(&[0x28, 0xb5, 0x2f, 0xfd])
+}
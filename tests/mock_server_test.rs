@@ -0,0 +1,2054 @@
+use scroll_proving_sdk::prover::{
+    proving_service::{
+        GetVkRequest, ProveRequest, QueryTaskRequest, QueryTaskResponse, TaskStatus,
+    },
+    CircuitType, ProvingService,
+};
+use sindri_scroll_sdk::credentials::CredentialProvider;
+use sindri_scroll_sdk::error::CloudProverError;
+use sindri_scroll_sdk::metrics::MetricsRecorder;
+use sindri_scroll_sdk::prover::{
+    input_sha256, CloudProver, CloudProverConfig, EstimateInfo, ListProofsParams, RateLimit,
+    RequestCompression, ResponseEncoding, SindriTaskStatus, DRY_RUN_TASK_ID,
+};
+use sindri_scroll_sdk::signing::RequestSigner;
+use sindri_scroll_sdk::test_util::{
+    circuit_info, circuit_path, json_response, proof_info, verify_info, MockSindri, Request,
+    ResponseTemplate,
+};
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const CIRCUIT_VERSION: &str = "v0.13.1";
+
+fn test_config(mock: &MockSindri) -> CloudProverConfig {
+    let cfg = CloudProverConfig::from_file("tests/test_data/default_config.json".to_string())
+        .expect("Issue loading test configuration file");
+    mock.configure(cfg)
+}
+
+// Start a mock server along with a prover pointed at it, with `configure` applied to the test
+// config.  Routes can be mounted on the mock after the prover is built.
+async fn setup(configure: impl FnOnce(&mut CloudProverConfig)) -> (MockSindri, CloudProver) {
+    let mock = MockSindri::start().await;
+    let mut cfg = test_config(&mock);
+    configure(&mut cfg);
+    let prover = CloudProver::try_new(cfg).unwrap();
+    (mock, prover)
+}
+
+// Accept chunk submissions as the queued task `proof_id`.
+async fn mount_prove(mock: &MockSindri, proof_id: &str) {
+    mock.mock_prove(
+        CircuitType::Chunk,
+        CIRCUIT_VERSION,
+        json_response(proof_info(proof_id, "Queued")),
+    )
+    .await;
+}
+
+// Answer the chunk circuit detail, which `ping` requests, with a vk.
+async fn mount_circuit(mock: &MockSindri) {
+    mock.mock_circuit_detail(
+        CircuitType::Chunk,
+        CIRCUIT_VERSION,
+        json_response(circuit_info("AAEC")),
+    )
+    .await;
+}
+
+// Report the task `proof_id` with the given status.
+async fn mount_status(mock: &MockSindri, proof_id: &str, status: &str) {
+    mock.mock_proof_detail(proof_id, json_response(proof_info(proof_id, status)))
+        .await;
+}
+
+fn prove_path() -> String {
+    circuit_path(CircuitType::Chunk, CIRCUIT_VERSION, "prove")
+}
+
+fn detail_path(proof_id: &str) -> String {
+    format!("/api/v1/proof/{proof_id}/detail")
+}
+
+fn chunk_request() -> ProveRequest {
+    ProveRequest {
+        circuit_type: CircuitType::Chunk,
+        circuit_version: CIRCUIT_VERSION.to_string(),
+        hard_fork_name: "darwinV2".to_string(),
+        input: "[{}]".to_string(),
+    }
+}
+
+fn query_request(task_id: &str) -> QueryTaskRequest {
+    QueryTaskRequest {
+        task_id: task_id.to_string(),
+    }
+}
+
+async fn query(prover: &CloudProver, task_id: &str) -> QueryTaskResponse {
+    prover.query_task(query_request(task_id)).await
+}
+
+// The query parameters of a request received by the mock.
+fn query_params(req: &Request) -> HashMap<String, String> {
+    req.url.query_pairs().into_owned().collect()
+}
+
+// `body` compressed with the given content encoding.
+async fn compress(encoding: &str, body: &[u8]) -> Vec<u8> {
+    use async_compression::tokio::write::GzipEncoder;
+    #[cfg(feature = "zstd")]
+    use async_compression::tokio::write::ZstdEncoder;
+    use tokio::io::AsyncWriteExt;
+
+    match encoding {
+        "gzip" => {
+            let mut encoder = GzipEncoder::new(Vec::new());
+            encoder.write_all(body).await.unwrap();
+            encoder.shutdown().await.unwrap();
+            encoder.into_inner()
+        }
+        #[cfg(feature = "zstd")]
+        "zstd" => {
+            let mut encoder = ZstdEncoder::new(Vec::new());
+            encoder.write_all(body).await.unwrap();
+            encoder.shutdown().await.unwrap();
+            encoder.into_inner()
+        }
+        _ => panic!("unsupported encoding {encoding}"),
+    }
+}
+
+// Lines logged through the `log` crate by any test in this file.
+static LOGS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+struct CaptureLogger;
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Debug
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            LOGS.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+// Start capturing log lines.  Tests running in parallel share the logger, so each one tells its
+// lines apart by a `log_label` of its own.
+fn capture_logs() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&CaptureLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+    });
+}
+
+// The captured lines logged by a prover with the given `log_label`.
+fn logs_for(label: &str) -> Vec<String> {
+    let prefix = format!("[Sindri client:{label}]");
+    LOGS.lock()
+        .unwrap()
+        .iter()
+        .filter(|line| line.starts_with(&prefix))
+        .cloned()
+        .collect()
+}
+
+// Verify a full round trip against the mock server: fetching a vk, submitting a proof and
+// polling it until it is ready.
+#[tokio::test]
+async fn test_mock_success() {
+    let (mock, prover) = setup(|_| {}).await;
+    mount_circuit(&mock).await;
+    mount_prove(&mock, "proof-1").await;
+    mount_status(&mock, "proof-1", "Ready").await;
+
+    let vks = prover
+        .get_vks(GetVkRequest {
+            circuit_types: vec![CircuitType::Chunk],
+            circuit_version: CIRCUIT_VERSION.to_string(),
+        })
+        .await;
+    assert_eq!(vks.error, None);
+    assert_eq!(vks.vks, vec!["AAEC".to_string()]);
+
+    let prove = prover.prove(chunk_request()).await;
+    assert_eq!(prove.error, None);
+    assert_eq!(prove.task_id, "proof-1");
+    assert!(matches!(prove.status, TaskStatus::Queued));
+
+    let (query, metadata) = prover
+        .query_task_with_metadata(query_request("proof-1"))
+        .await;
+    assert_eq!(query.error, None);
+    assert!(matches!(query.status, TaskStatus::Success));
+    assert_eq!(query.circuit_type, CircuitType::Chunk);
+    assert_eq!(query.circuit_version, CIRCUIT_VERSION);
+    assert!(query.proof.is_some());
+    assert!(metadata.public.is_some());
+}
+
+// Verify that a rate-limited submission is retried and then succeeds.
+#[tokio::test]
+async fn test_mock_rate_limited() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_times("POST", &prove_path(), 1, ResponseTemplate::new(429))
+        .await;
+    mount_prove(&mock, "proof-1").await;
+
+    let prove = prover.prove(chunk_request()).await;
+    assert_eq!(prove.error, None);
+    assert_eq!(prove.task_id, "proof-1");
+    assert_eq!(mock.received_requests().await, 2);
+    assert_eq!(prover.total_retries(), 1);
+}
+
+// Verify that a persistent server error exhausts the retries and is reported as a failed task,
+// labelled with the client prefix once.
+#[tokio::test]
+async fn test_mock_server_error() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_prove(
+        CircuitType::Chunk,
+        CIRCUIT_VERSION,
+        ResponseTemplate::new(500),
+    )
+    .await;
+    let retry_count = test_config(&mock).retry_count as usize;
+
+    let prove = prover.prove(chunk_request()).await;
+    assert!(matches!(prove.status, TaskStatus::Failed));
+    let error = prove.error.unwrap();
+    assert!(error.contains("500"));
+    assert!(error.starts_with("[Sindri client], "), "{error}");
+    assert_eq!(error.matches("[Sindri client]").count(), 1, "{error}");
+    assert_eq!(mock.received_requests().await, retry_count + 1);
+    assert_eq!(prover.total_retries(), retry_count as u64);
+}
+
+// Verify that a malformed response body is reported as an error instead of a panic.
+#[tokio::test]
+async fn test_mock_malformed_json() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_proof_detail(
+        "proof-1",
+        ResponseTemplate::new(200).set_body_string("{\"proof_id\": "),
+    )
+    .await;
+
+    let query = query(&prover, "proof-1").await;
+    assert!(query.error.unwrap().contains("decode"));
+}
+
+// Hands out a fresh token per call, failing once `limit` tokens have been issued.
+struct RotatingCredentials {
+    issued: AtomicU32,
+    limit: u32,
+}
+
+#[async_trait::async_trait]
+impl CredentialProvider for RotatingCredentials {
+    async fn token(&self) -> anyhow::Result<String> {
+        let issued = self.issued.fetch_add(1, Ordering::SeqCst);
+        anyhow::ensure!(issued < self.limit, "secret manager unavailable");
+        Ok(format!("token-{issued}"))
+    }
+}
+
+// Verify that the credential provider is consulted on every request and that its failures are
+// reported as credential errors.
+#[tokio::test]
+async fn test_mock_credential_provider() {
+    let (mock, prover) = setup(|_| {}).await;
+    mount_circuit(&mock).await;
+    let credentials = Arc::new(RotatingCredentials {
+        issued: AtomicU32::new(0),
+        limit: 2,
+    });
+    let prover = prover.with_credential_provider(credentials.clone());
+
+    prover.ping().await.unwrap();
+    prover.ping().await.unwrap();
+    assert_eq!(credentials.issued.load(Ordering::SeqCst), 2);
+    assert!(matches!(
+        prover.ping().await,
+        Err(CloudProverError::Credentials(_))
+    ));
+    assert_eq!(mock.received_requests().await, 2);
+}
+
+// Signs each request with a digest of what it was asked to sign.
+struct DigestSigner;
+
+#[async_trait::async_trait]
+impl RequestSigner for DigestSigner {
+    async fn sign(
+        &self,
+        method: &reqwest::Method,
+        path_and_query: &str,
+        body: &[u8],
+    ) -> anyhow::Result<reqwest::header::HeaderMap> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            "x-signature",
+            format!("{method} {path_and_query} body={}", hex::encode(body)).parse()?,
+        );
+        Ok(headers)
+    }
+}
+
+// Verify that the request signer sees the method, target and body of each request as sent,
+// recomputing the signature from what the mock received, compressed bodies included.
+#[tokio::test]
+async fn test_mock_request_signer() {
+    let mut cases = vec![
+        (RequestCompression::None, None),
+        (RequestCompression::Gzip, Some("gzip")),
+    ];
+    if cfg!(feature = "zstd") {
+        cases.push((RequestCompression::Zstd, Some("zstd")));
+    }
+    for (compression, encoding) in cases {
+        let (mock, prover) = setup(|cfg| cfg.request_compression = compression).await;
+        mount_prove(&mock, "proof-1").await;
+        mount_status(&mock, "proof-1", "Queued").await;
+        let prover = prover.with_request_signer(Arc::new(DigestSigner));
+
+        let req = ProveRequest {
+            input: format!("[{}]", vec!["{}"; 1000].join(",")),
+            ..chunk_request()
+        };
+        assert_eq!(prover.prove(req).await.task_id, "proof-1");
+        query(&prover, "proof-1").await;
+
+        let requests = mock.requests().await;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0]
+                .headers
+                .get("content-encoding")
+                .map(|value| value.to_str().unwrap()),
+            encoding
+        );
+        for req in requests {
+            let target = match req.url.query() {
+                Some(query) => format!("{}?{query}", req.url.path()),
+                None => req.url.path().to_string(),
+            };
+            assert_eq!(
+                req.headers["x-signature"],
+                format!("{} {target} body={}", req.method, hex::encode(&req.body))
+            );
+        }
+    }
+}
+
+// Verify that a rotated API key is sent with subsequent requests, including those of clones.
+#[tokio::test]
+async fn test_mock_set_api_key() {
+    let (mock, prover) = setup(|_| {}).await;
+    mount_circuit(&mock).await;
+    let old_key = test_config(&mock).api_key;
+    let clone = prover.clone();
+
+    prover.ping().await.unwrap();
+    prover.set_api_key("rotated-key".to_string());
+    prover.ping().await.unwrap();
+    clone.ping().await.unwrap();
+
+    let tokens: Vec<String> = mock
+        .requests()
+        .await
+        .iter()
+        .map(|req| req.headers["authorization"].to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            format!("Bearer {old_key}"),
+            "Bearer rotated-key".to_string(),
+            "Bearer rotated-key".to_string(),
+        ]
+    );
+}
+
+// Verify that large request bodies are sent with the configured compression, and that a
+// precompressed body is sent as is, labelled with its encoding, rather than compressed again.
+#[tokio::test]
+async fn test_mock_request_compression() {
+    let input = format!("[{}]", vec!["{}"; 1000].join(","));
+    let body = serde_json::to_vec(&serde_json::json!({
+        "perform_verify": true,
+        "proof_input": input,
+    }))
+    .unwrap();
+    let precompressed = compress("gzip", &body).await;
+    let mut cases = vec![
+        (
+            RequestCompression::Gzip,
+            None,
+            Some("gzip"),
+            &[0x1f, 0x8b][..],
+        ),
+        (RequestCompression::None, None, None, &b"{"[..]),
+        (
+            RequestCompression::default(),
+            Some(precompressed.clone()),
+            Some("gzip"),
+            &[0x1f, 0x8b][..],
+        ),
+    ];
+    if cfg!(feature = "zstd") {
+        cases.push((
+            RequestCompression::Zstd,
+            None,
+            Some("zstd"),
+            &[0x28, 0xb5, 0x2f, 0xfd][..],
+        ));
+    }
+    for (compression, precompressed, encoding, magic) in cases {
+        let (mock, prover) = setup(|cfg| cfg.request_compression = compression).await;
+        mount_prove(&mock, "proof-1").await;
+
+        let req = ProveRequest {
+            input: input.clone(),
+            ..chunk_request()
+        };
+        let resp = match &precompressed {
+            Some(body) => {
+                let (resp, _) = prover
+                    .prove_precompressed(req, body.clone(), RequestCompression::Gzip)
+                    .await;
+                assert_eq!(resp.input, None);
+                resp
+            }
+            None => prover.prove(req).await,
+        };
+        assert_eq!(resp.error, None);
+        assert_eq!(resp.task_id, "proof-1");
+
+        let requests = mock.requests().await;
+        assert_eq!(requests.len(), 1);
+        let content_encoding = requests[0]
+            .headers
+            .get("content-encoding")
+            .map(|value| value.to_str().unwrap());
+        assert_eq!(content_encoding, encoding);
+        assert!(requests[0].body.starts_with(magic));
+        if let Some(body) = precompressed {
+            assert_eq!(requests[0].body, body);
+        }
+    }
+}
+
+// Verify that without the `zstd` feature requests default to gzip and responses to uncompressed.
+#[cfg(not(feature = "zstd"))]
+#[tokio::test]
+async fn test_mock_default_compression_without_zstd() {
+    assert_eq!(RequestCompression::default(), RequestCompression::Gzip);
+    let (mock, prover) = setup(|_| {}).await;
+    assert!(!test_config(&mock).response_zstd);
+    mount_prove(&mock, "proof-1").await;
+
+    let req = ProveRequest {
+        input: format!("[{}]", vec!["{}"; 1000].join(",")),
+        ..chunk_request()
+    };
+    assert_eq!(prover.prove(req).await.error, None);
+    let requests = mock.requests().await;
+    assert_eq!(requests[0].headers["content-encoding"], "gzip");
+    assert!(requests[0].headers.get("accept-encoding").is_none());
+}
+
+// Verify the `Accept-Encoding` sent for `response_zstd` and `accept_encodings`, in order of
+// preference, and that responses in an accepted encoding are decompressed.
+#[tokio::test]
+async fn test_mock_response_encoding() {
+    let body = serde_json::to_vec(&circuit_info("AAEC")).unwrap();
+    let mut cases = vec![
+        (false, None, None, None),
+        (
+            false,
+            Some(vec![ResponseEncoding::Gzip]),
+            Some("gzip"),
+            Some("gzip"),
+        ),
+    ];
+    if cfg!(feature = "zstd") {
+        cases.push((true, None, Some("zstd"), Some("zstd")));
+        cases.push((
+            true,
+            Some(vec![ResponseEncoding::Gzip, ResponseEncoding::Zstd]),
+            Some("gzip, zstd;q=0.9"),
+            Some("gzip"),
+        ));
+    }
+    for (response_zstd, accept_encodings, accept_encoding, content_encoding) in cases {
+        let (mock, prover) = setup(|cfg| {
+            cfg.response_zstd = response_zstd;
+            cfg.accept_encodings = accept_encodings;
+        })
+        .await;
+        let response = match content_encoding {
+            Some(encoding) => ResponseTemplate::new(200)
+                .insert_header("content-encoding", encoding)
+                .set_body_bytes(compress(encoding, &body).await),
+            None => ResponseTemplate::new(200).set_body_bytes(body.clone()),
+        };
+        mock.mock_circuit_detail(CircuitType::Chunk, CIRCUIT_VERSION, response)
+            .await;
+
+        let detail = prover.circuit_detail(CircuitType::Chunk).await.unwrap();
+        assert_eq!(detail.vk().unwrap(), Some("AAEC".to_string()));
+        let requests = mock.requests().await;
+        let sent = requests[0]
+            .headers
+            .get("accept-encoding")
+            .map(|value| value.to_str().unwrap());
+        assert_eq!(sent, accept_encoding);
+    }
+
+    if cfg!(not(feature = "brotli")) {
+        let mock = MockSindri::start().await;
+        let mut cfg = test_config(&mock);
+        cfg.accept_encodings = Some(vec![ResponseEncoding::Br]);
+        assert!(matches!(
+            CloudProver::try_new(cfg),
+            Err(CloudProverError::InvalidConfig(_))
+        ));
+    }
+}
+
+// Verify that both the size of a zstd response on the wire and its decompressed size are logged.
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn test_mock_response_size_logging() {
+    capture_logs();
+    let body = serde_json::to_vec(&circuit_info(&"AAEC".repeat(1_000))).unwrap();
+    let compressed = compress("zstd", &body).await;
+    assert!(compressed.len() < body.len());
+    let (mock, prover) = setup(|cfg| cfg.log_label = Some("size-logging".to_string())).await;
+    mock.mock_circuit_detail(
+        CircuitType::Chunk,
+        CIRCUIT_VERSION,
+        ResponseTemplate::new(200)
+            .insert_header("content-encoding", "zstd")
+            .set_body_bytes(compressed.clone()),
+    )
+    .await;
+
+    prover.circuit_detail(CircuitType::Chunk).await.unwrap();
+    let expected = format!(
+        "response body: {} bytes on the wire, {} bytes decompressed",
+        compressed.len(),
+        body.len()
+    );
+    let logs = logs_for("size-logging");
+    assert!(
+        logs.iter().any(|line| line.ends_with(&expected)),
+        "{logs:?}"
+    );
+}
+
+// Verify that a zstd response body that breaks off mid-stream is reported as an incomplete body,
+// which is worth retrying, rather than as malformed JSON.
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn test_mock_truncated_zstd_response() {
+    // Both a short body and one spanning several zstd blocks.
+    for vk in ["AAEC".to_string(), "AAEC".repeat(100_000)] {
+        let body = serde_json::to_vec(&circuit_info(&vk)).unwrap();
+        let compressed = compress("zstd", &body).await;
+        let truncated = compressed[..compressed.len() / 2].to_vec();
+
+        let (mock, prover) = setup(|_| {}).await;
+        mock.mock_circuit_detail(
+            CircuitType::Chunk,
+            CIRCUIT_VERSION,
+            ResponseTemplate::new(200)
+                .insert_header("content-encoding", "zstd")
+                .set_body_bytes(truncated),
+        )
+        .await;
+
+        let err = prover.circuit_detail(CircuitType::Chunk).await.unwrap_err();
+        assert!(
+            matches!(err, CloudProverError::IncompleteBody(_)),
+            "{err:?}"
+        );
+        assert!(err.is_retryable());
+    }
+}
+
+// Verify that requests are sent below the path of `base_url`, with or without a trailing slash
+// and regardless of slashes around `api_path`, and that circuit routes use the configured circuit
+// namespace.
+#[tokio::test]
+async fn test_mock_request_path() {
+    for (base_path, api_path, namespace, expected) in [
+        (
+            "",
+            "/sindri/api/v2",
+            "scroll-tech",
+            "/sindri/api/v2/circuit/scroll-tech/chunk_prover:v0.13.1/detail",
+        ),
+        (
+            "",
+            "/api/v1/",
+            "my-fork",
+            "/api/v1/circuit/my-fork/chunk_prover:v0.13.1/detail",
+        ),
+        (
+            "/gateway",
+            "/api/v1/",
+            "scroll-tech",
+            "/gateway/api/v1/circuit/scroll-tech/chunk_prover:v0.13.1/detail",
+        ),
+        (
+            "/gateway/",
+            "/api/v1/",
+            "scroll-tech",
+            "/gateway/api/v1/circuit/scroll-tech/chunk_prover:v0.13.1/detail",
+        ),
+        (
+            "/gateway",
+            "api/v1",
+            "scroll-tech",
+            "/gateway/api/v1/circuit/scroll-tech/chunk_prover:v0.13.1/detail",
+        ),
+        (
+            "/gateway/",
+            "api/v1/",
+            "scroll-tech",
+            "/gateway/api/v1/circuit/scroll-tech/chunk_prover:v0.13.1/detail",
+        ),
+    ] {
+        let mock = MockSindri::start().await;
+        let mut cfg = test_config(&mock);
+        cfg.base_url = format!("{}{base_path}", mock.uri());
+        cfg.api_path = api_path.to_string();
+        cfg.circuit_namespace = namespace.to_string();
+        let prover = CloudProver::try_new(cfg).unwrap();
+
+        // Nothing is mounted, so the request itself fails but is still recorded.
+        assert!(prover.ping().await.is_err());
+        let requests = mock.requests().await;
+        assert_eq!(
+            requests[0].url.path(),
+            expected,
+            "base path {base_path:?}, api path {api_path:?}"
+        );
+    }
+}
+
+// Verify that waiting on a task polls through non-terminal states until it is ready, fetching the
+// proof only once it is.
+#[tokio::test]
+async fn test_mock_wait_for_task() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_times(
+        "GET",
+        &detail_path("proof-1"),
+        2,
+        json_response(proof_info("proof-1", "In Progress")),
+    )
+    .await;
+    mount_status(&mock, "proof-1", "Ready").await;
+
+    let resp = prover
+        .wait_for_task("proof-1", Duration::from_millis(10), Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(matches!(resp.status, TaskStatus::Success));
+
+    let include_proof: Vec<String> = mock
+        .requests()
+        .await
+        .iter()
+        .map(|req| query_params(req)["include_proof"].clone())
+        .collect();
+    assert_eq!(include_proof, ["false", "false", "false", "true"]);
+}
+
+// Verify that waiting on a task that never finishes ends in a timeout error carrying its status.
+#[tokio::test]
+async fn test_mock_wait_for_task_timeout() {
+    let (mock, prover) = setup(|_| {}).await;
+    mount_status(&mock, "proof-1", "Queued").await;
+
+    let result = prover
+        .wait_for_task(
+            "proof-1",
+            Duration::from_millis(10),
+            Duration::from_millis(50),
+        )
+        .await;
+    let Err(err) = result else {
+        panic!("waiting on a queued task succeeded");
+    };
+    assert!(err.to_string().ends_with("last status: Queued"));
+    assert!(matches!(
+        err,
+        CloudProverError::TaskTimeout {
+            task_id,
+            last_status: Some(SindriTaskStatus::Queued),
+            ..
+        } if task_id == "proof-1"
+    ));
+}
+
+// Verify that vks of several circuit types are returned sorted and deduplicated whatever the
+// requested order, and that a failure for one circuit type is reported along with the circuit type.
+#[tokio::test]
+async fn test_mock_get_vks_multiple() {
+    let (mock, prover) = setup(|_| {}).await;
+    for (circuit_type, vk) in [
+        (CircuitType::Chunk, "AAEC"),
+        (CircuitType::Batch, "AwQF"),
+        (CircuitType::Bundle, "AAEC"),
+    ] {
+        mock.mock_circuit_detail(
+            circuit_type,
+            CIRCUIT_VERSION,
+            json_response(circuit_info(vk)),
+        )
+        .await;
+    }
+    let get_vks = |circuit_types| {
+        prover.get_vks(GetVkRequest {
+            circuit_types,
+            circuit_version: CIRCUIT_VERSION.to_string(),
+        })
+    };
+
+    let vks = get_vks(vec![
+        CircuitType::Chunk,
+        CircuitType::Batch,
+        CircuitType::Bundle,
+    ])
+    .await;
+    assert_eq!(vks.error, None);
+    assert_eq!(vks.vks, vec!["AAEC".to_string(), "AwQF".to_string()]);
+
+    let reordered = get_vks(vec![
+        CircuitType::Batch,
+        CircuitType::Bundle,
+        CircuitType::Chunk,
+    ])
+    .await;
+    assert_eq!(reordered.vks, vks.vks);
+
+    let vks = get_vks(vec![CircuitType::Chunk, CircuitType::Undefined]).await;
+    assert!(vks.error.unwrap().contains("Undefined"));
+    assert_eq!(vks.vks, vec!["AAEC".to_string()]);
+
+    let vks = prover
+        .vks_by_circuit(GetVkRequest {
+            circuit_types: vec![
+                CircuitType::Undefined,
+                CircuitType::Chunk,
+                CircuitType::Bundle,
+            ],
+            circuit_version: CIRCUIT_VERSION.to_string(),
+        })
+        .await;
+    assert_eq!(vks[0].circuit_type, CircuitType::Undefined);
+    assert!(matches!(
+        vks[0].vk,
+        Err(CloudProverError::UndefinedCircuitType)
+    ));
+    assert_eq!(vks[1].circuit_type, CircuitType::Chunk);
+    assert_eq!(vks[1].vk.as_deref().unwrap(), "AAEC");
+    assert_eq!(vks[2].circuit_type, CircuitType::Bundle);
+    assert_eq!(vks[2].vk.as_deref().unwrap(), "AAEC");
+}
+
+// Verify that vks are fetched once and then served from the cache, until `vk_cache_ttl_sec` has
+// passed since they were fetched.
+#[tokio::test]
+async fn test_mock_vk_cache() {
+    for (vk_cache_ttl_sec, fetches) in [(None, 1), (Some(1), 2)] {
+        let (mock, prover) = setup(|cfg| cfg.vk_cache_ttl_sec = vk_cache_ttl_sec).await;
+        mock.mock_expect(
+            "GET",
+            &circuit_path(CircuitType::Chunk, CIRCUIT_VERSION, "detail"),
+            fetches,
+            json_response(circuit_info("AAEC")),
+        )
+        .await;
+        let get_vks = || {
+            prover.get_vks(GetVkRequest {
+                circuit_types: vec![CircuitType::Chunk],
+                circuit_version: CIRCUIT_VERSION.to_string(),
+            })
+        };
+
+        for _ in 0..2 {
+            assert_eq!(get_vks().await.vks, vec!["AAEC".to_string()]);
+        }
+        assert_eq!(mock.received_requests().await, 1);
+
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert_eq!(get_vks().await.vks, vec!["AAEC".to_string()]);
+        assert_eq!(mock.received_requests().await, fetches as usize);
+    }
+}
+
+// Verify that submitted inputs are echoed by `query_task` when enabled, oldest evicted first, and
+// that clones of a prover moved into spawned tasks share them.
+#[tokio::test]
+async fn test_mock_input_echo() {
+    let (mock, prover) = setup(|cfg| cfg.input_echo_capacity = 1).await;
+    mock.mock_times(
+        "POST",
+        &prove_path(),
+        1,
+        json_response(proof_info("proof-1", "Queued")),
+    )
+    .await;
+    mount_prove(&mock, "proof-2").await;
+    for proof_id in ["proof-1", "proof-2"] {
+        mount_status(&mock, proof_id, "Ready").await;
+    }
+
+    let clone = prover.clone();
+    let resp = tokio::spawn(async move { clone.prove(chunk_request()).await })
+        .await
+        .unwrap();
+    assert_eq!(resp.task_id, "proof-1");
+    assert_eq!(
+        query(&prover, "proof-1").await.input,
+        Some("[{}]".to_string())
+    );
+
+    let req = ProveRequest {
+        input: "[{}, {}]".to_string(),
+        ..chunk_request()
+    };
+    prover.prove(req).await;
+    assert_eq!(query(&prover, "proof-1").await.input, None);
+    assert_eq!(
+        query(&prover, "proof-2").await.input,
+        Some("[{}, {}]".to_string())
+    );
+}
+
+// Verify that configured extra headers are sent with both GET and POST requests.
+#[tokio::test]
+async fn test_mock_extra_headers() {
+    let (mock, prover) = setup(|cfg| {
+        cfg.extra_headers
+            .insert("X-Tenant".to_string(), "scroll-sdk".to_string());
+    })
+    .await;
+    mount_circuit(&mock).await;
+    mount_prove(&mock, "proof-1").await;
+
+    prover.ping().await.unwrap();
+    assert_eq!(prover.prove(chunk_request()).await.error, None);
+
+    let requests = mock.requests().await;
+    assert_eq!(requests.len(), 2);
+    for request in requests {
+        assert_eq!(request.headers.get("x-tenant").unwrap(), "scroll-sdk");
+    }
+
+    let mut cfg = test_config(&mock);
+    cfg.extra_headers
+        .insert("X-Tenant".to_string(), "bad\nvalue".to_string());
+    assert!(matches!(
+        CloudProver::try_new(cfg),
+        Err(CloudProverError::InvalidConfig(_))
+    ));
+}
+
+// Verify that a response larger than `max_response_bytes` is rejected instead of being buffered.
+#[tokio::test]
+async fn test_mock_max_response_bytes() {
+    for (max_response_bytes, rejected) in [(1024, true), (8192, false)] {
+        let (mock, prover) = setup(|cfg| cfg.max_response_bytes = Some(max_response_bytes)).await;
+        mock.mock_circuit_detail(
+            CircuitType::Chunk,
+            CIRCUIT_VERSION,
+            json_response(circuit_info(&"A".repeat(4096))),
+        )
+        .await;
+
+        match prover.ping().await {
+            Err(CloudProverError::ResponseTooLarge { limit }) => {
+                assert!(rejected);
+                assert_eq!(limit, max_response_bytes);
+            }
+            result => assert!(!rejected && result.is_ok(), "{result:?}"),
+        }
+    }
+}
+
+// Verify that inputs above `max_input_bytes` are refused without being sent, while smaller ones are
+// submitted as usual.
+#[tokio::test]
+async fn test_mock_max_input_bytes() {
+    let (mock, prover) = setup(|cfg| cfg.max_input_bytes = Some(100)).await;
+    mount_prove(&mock, "proof-1").await;
+
+    let req = ProveRequest {
+        input: format!("[{}]", vec!["{}"; 100].join(",")),
+        ..chunk_request()
+    };
+    let resp = prover.prove(req).await;
+    assert!(matches!(resp.status, TaskStatus::Failed));
+    assert!(resp
+        .error
+        .unwrap()
+        .contains("exceeds the limit of 100 bytes"));
+    assert_eq!(mock.received_requests().await, 0);
+
+    assert_eq!(prover.prove(chunk_request()).await.task_id, "proof-1");
+    assert_eq!(mock.received_requests().await, 1);
+
+    let mut cfg = test_config(&mock);
+    cfg.max_input_bytes = Some(0);
+    assert!(matches!(
+        CloudProver::try_new(cfg),
+        Err(CloudProverError::InvalidConfig(_))
+    ));
+}
+
+// Verify that dry-run mode processes the input locally without submitting anything to Sindri,
+// precompressed bodies included.
+#[tokio::test]
+async fn test_mock_dry_run() {
+    let (mock, prover) = setup(|cfg| cfg.dry_run = true).await;
+
+    let prove = prover.prove(chunk_request()).await;
+    assert_eq!(prove.error, None);
+    assert_eq!(prove.task_id, DRY_RUN_TASK_ID);
+
+    // Local validation still applies.
+    let req = ProveRequest {
+        input: "[]".to_string(),
+        ..chunk_request()
+    };
+    let prove = prover.prove(req).await;
+    assert!(matches!(prove.status, TaskStatus::Failed));
+
+    // Precompressed bodies are not inspected, but still need a circuit to be sent to.
+    let (prove, _) = prover
+        .prove_precompressed(chunk_request(), vec![0; 16], RequestCompression::Gzip)
+        .await;
+    assert_eq!(prove.error, None);
+    assert_eq!(prove.task_id, DRY_RUN_TASK_ID);
+    assert_eq!(prove.input, None);
+    let req = ProveRequest {
+        circuit_type: CircuitType::Undefined,
+        ..chunk_request()
+    };
+    let (prove, _) = prover
+        .prove_precompressed(req, vec![0; 16], RequestCompression::Gzip)
+        .await;
+    assert!(matches!(prove.status, TaskStatus::Failed));
+
+    assert_eq!(mock.received_requests().await, 0);
+}
+
+// Verify that proofs are verified through Sindri without downloading them, and that unfinished
+// tasks cannot be verified.
+#[tokio::test]
+async fn test_mock_verify_proof() {
+    let (mock, prover) = setup(|_| {}).await;
+    for (proof_id, status, valid) in [
+        ("proof-1", "Ready", true),
+        ("proof-2", "Ready", false),
+        ("proof-3", "In Progress", true),
+    ] {
+        mount_status(&mock, proof_id, status).await;
+        mock.mock_proof_verify(proof_id, json_response(verify_info(valid)))
+            .await;
+    }
+
+    assert!(prover.verify_proof("proof-1").await.unwrap());
+    assert!(!prover.verify_proof("proof-2").await.unwrap());
+    assert!(matches!(
+        prover.verify_proof("proof-3").await,
+        Err(CloudProverError::ProofUnavailable { .. })
+    ));
+    let requests = mock.requests().await;
+    let detail = requests
+        .iter()
+        .find(|req| req.url.path() == detail_path("proof-1"))
+        .unwrap();
+    assert_eq!(query_params(detail)["include_proof"], "false");
+}
+
+// Verify that cancelling a task deletes its proof, and that an unknown task is reported as such.
+#[tokio::test]
+async fn test_mock_cancel_task() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_proof_delete("proof-1", json_response(serde_json::json!({})))
+        .await;
+    mock.mock_proof_delete("proof-2", ResponseTemplate::new(404))
+        .await;
+
+    prover.cancel_task("proof-1").await.unwrap();
+    let requests = mock.requests().await;
+    assert_eq!(requests[0].method.as_str(), "DELETE");
+    assert_eq!(requests[0].url.path(), "/api/v1/proof/proof-1/delete");
+
+    assert!(matches!(
+        prover.cancel_task("proof-2").await,
+        Err(CloudProverError::HttpStatus { status, .. }) if status == reqwest::StatusCode::NOT_FOUND
+    ));
+    assert_eq!(mock.received_requests().await, 2);
+}
+
+// Verify that batched submissions are answered in the order they were given.
+#[tokio::test]
+async fn test_mock_prove_many() {
+    let (mock, prover) = setup(|_| {}).await;
+    mount_prove(&mock, "proof-1").await;
+
+    let inputs: Vec<String> = (1..=5)
+        .map(|n| format!("[{}]", vec!["{}"; n].join(",")))
+        .collect();
+    let reqs = inputs
+        .iter()
+        .map(|input| ProveRequest {
+            input: input.clone(),
+            ..chunk_request()
+        })
+        .collect();
+
+    let responses = prover.prove_many(reqs, 2).await;
+    let echoed: Vec<String> = responses
+        .into_iter()
+        .map(|resp| {
+            assert_eq!(resp.error, None);
+            resp.input.unwrap()
+        })
+        .collect();
+    assert_eq!(echoed, inputs);
+    assert_eq!(mock.received_requests().await, 5);
+}
+
+// Verify that requests are sent through the configured proxy, with its credentials.
+#[tokio::test]
+async fn test_mock_proxy() {
+    // The mock server stands in for the proxy: a proxied request carries the absolute target URL.
+    let proxy = MockSindri::start().await;
+    let mut cfg = test_config(&proxy);
+    cfg.base_url = "http://sindri.invalid".to_string();
+    cfg.proxy_url = Some(proxy.uri());
+    cfg.proxy_username = Some("user".to_string());
+    cfg.proxy_password = Some("secret".to_string());
+    let prover = CloudProver::try_new(cfg.clone()).unwrap();
+
+    assert!(prover.ping().await.is_err());
+    let requests = proxy.requests().await;
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].headers.contains_key("proxy-authorization"));
+    assert!(!format!("{cfg:?}").contains("secret"));
+
+    cfg.proxy_url = Some("not a proxy".to_string());
+    assert!(matches!(
+        CloudProver::try_new(cfg),
+        Err(CloudProverError::InvalidConfig(_))
+    ));
+}
+
+// Verify the default user agent and that it can be overridden.
+#[tokio::test]
+async fn test_mock_user_agent() {
+    let default_user_agent = format!(
+        "sindri-scroll-sdk/{} (circuit v0.13.1)",
+        env!("CARGO_PKG_VERSION")
+    );
+    for (user_agent, expected) in [
+        (None, default_user_agent.as_str()),
+        (Some("my-prover/2.0".to_string()), "my-prover/2.0"),
+    ] {
+        let (mock, prover) = setup(|cfg| cfg.user_agent = user_agent).await;
+        mount_circuit(&mock).await;
+        prover.ping().await.unwrap();
+
+        let requests = mock.requests().await;
+        assert_eq!(requests[0].headers.get("user-agent").unwrap(), expected);
+    }
+}
+
+// Verify that connecting fails fast when the API key is rejected.
+#[tokio::test]
+async fn test_mock_connect() {
+    let mock = MockSindri::start().await;
+    mount_circuit(&mock).await;
+    assert!(CloudProver::connect(test_config(&mock)).await.is_ok());
+
+    let mock = MockSindri::start().await;
+    mock.mock_circuit_detail(
+        CircuitType::Chunk,
+        CIRCUIT_VERSION,
+        ResponseTemplate::new(401),
+    )
+    .await;
+    assert!(matches!(
+        CloudProver::connect(test_config(&mock)).await,
+        Err(CloudProverError::Unauthorized)
+    ));
+}
+
+// Verify that a prover with tuned connection pooling still reaches Sindri.
+#[tokio::test]
+async fn test_mock_connection_pool() {
+    let (mock, prover) = setup(|cfg| {
+        cfg.pool_max_idle_per_host = Some(64);
+        cfg.pool_idle_timeout_sec = Some(30);
+    })
+    .await;
+    mount_circuit(&mock).await;
+
+    for _ in 0..3 {
+        prover.ping().await.unwrap();
+    }
+    assert_eq!(mock.received_requests().await, 3);
+}
+
+// Verify that the full circuit detail is parsed, including its readiness and verification key.
+#[tokio::test]
+async fn test_mock_circuit_detail() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_circuit_detail(
+        CircuitType::Batch,
+        CIRCUIT_VERSION,
+        json_response(circuit_info("-_8")),
+    )
+    .await;
+
+    let detail = prover.circuit_detail(CircuitType::Batch).await.unwrap();
+    assert_eq!(detail.status, SindriTaskStatus::Success);
+    assert_eq!(detail.circuit_type.as_deref(), Some("halo2"));
+    assert_eq!(detail.proving_scheme.as_deref(), Some("shplonk"));
+    assert!(detail.created_at().is_ok());
+    assert_eq!(detail.vk().unwrap().as_deref(), Some("+/8="));
+}
+
+// Verify that a task reported as ready without a proof is treated as still proving instead of
+// yielding a `"null"` proof.
+#[tokio::test]
+async fn test_mock_ready_without_proof() {
+    let (mock, prover) = setup(|_| {}).await;
+    let mut body = proof_info("proof-1", "Ready");
+    body["proof"] = serde_json::Value::Null;
+    mock.mock_times("GET", &detail_path("proof-1"), 2, json_response(body))
+        .await;
+    mount_status(&mock, "proof-1", "Ready").await;
+
+    let resp = query(&prover, "proof-1").await;
+    assert!(matches!(resp.status, TaskStatus::Proving));
+    assert_eq!(resp.proof, None);
+
+    // Waiting keeps polling until the proof shows up.
+    let resp = prover
+        .wait_for_task("proof-1", Duration::from_millis(10), Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(matches!(resp.status, TaskStatus::Success));
+    assert_eq!(resp.proof.as_deref(), Some(r#"{"proof":"AAAA"}"#));
+}
+
+// Verify that warnings about statuses reported as another one carry the prover's log label.
+#[tokio::test]
+async fn test_mock_status_warning_prefix() {
+    capture_logs();
+    let (mock, prover) = setup(|cfg| cfg.log_label = Some("status-warnings".to_string())).await;
+    let mut body = proof_info("proof-1", "Ready");
+    body["proof"] = serde_json::Value::Null;
+    mock.mock_proof_detail("proof-1", json_response(body)).await;
+    mount_status(&mock, "proof-2", "Cancelled").await;
+
+    assert!(matches!(
+        query(&prover, "proof-1").await.status,
+        TaskStatus::Proving
+    ));
+    assert!(matches!(
+        query(&prover, "proof-2").await.status,
+        TaskStatus::Failed
+    ));
+    let logs = logs_for("status-warnings");
+    for warning in [
+        "proof proof-1 is ready but has no proof yet",
+        "unknown task status \"Cancelled\", treating as failed",
+    ] {
+        assert!(
+            logs.iter()
+                .any(|line| line == &format!("[Sindri client:status-warnings], {warning}")),
+            "{logs:?}"
+        );
+    }
+}
+
+// Verify that a labelled prover's errors and warnings, here from a failing prove request and a
+// proof without a usable creation time, never carry the unlabelled prefix.
+#[tokio::test]
+async fn test_mock_labelled_output() {
+    capture_logs();
+    let (mock, prover) = setup(|cfg| cfg.log_label = Some("labelled-output".to_string())).await;
+    mock.mock_prove(
+        CircuitType::Chunk,
+        CIRCUIT_VERSION,
+        ResponseTemplate::new(500).set_body_string("labelled-output failure"),
+    )
+    .await;
+    let mut body = proof_info("labelled-output-1", "Queued");
+    body["date_created"] = "not a date".into();
+    mock.mock_proof_detail("labelled-output-1", json_response(body))
+        .await;
+
+    let error = prover.prove(chunk_request()).await.error.unwrap();
+    assert!(
+        error.starts_with("[Sindri client:labelled-output], "),
+        "{error}"
+    );
+    assert!(!error.contains("[Sindri client]"), "{error}");
+    query(&prover, "labelled-output-1").await;
+
+    let logs: Vec<String> = LOGS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|line| line.contains("labelled-output"))
+        .cloned()
+        .collect();
+    assert!(logs
+        .iter()
+        .any(|line| line.contains("labelled-output failure")));
+    assert!(logs
+        .iter()
+        .any(|line| line.contains("proof labelled-output-1 has no usable creation time")));
+    for line in &logs {
+        assert!(
+            line.starts_with("[Sindri client:labelled-output]"),
+            "{line}"
+        );
+        assert!(!line.contains("[Sindri client]"), "{line}");
+    }
+}
+
+// Verify that the prove request body honours a renamed input field and configured extra fields,
+// and that per-request proving options are added to it, overriding the configured extra fields.
+#[tokio::test]
+async fn test_mock_prove_body() {
+    let options: HashMap<String, serde_json::Value> = [
+        ("priority".to_string(), serde_json::json!(1)),
+        ("tier".to_string(), serde_json::json!("fast")),
+    ]
+    .into_iter()
+    .collect();
+    for (input_field, extra_fields, options, expected) in [
+        (
+            "input",
+            serde_json::json!({ "perform_verify": false, "priority": 3 }),
+            None,
+            serde_json::json!({ "input": "[{}]", "perform_verify": false, "priority": 3 }),
+        ),
+        (
+            "proof_input",
+            serde_json::json!({ "priority": 3 }),
+            Some(options),
+            serde_json::json!({
+                "proof_input": "[{}]",
+                "perform_verify": true,
+                "priority": 1,
+                "tier": "fast",
+            }),
+        ),
+    ] {
+        let (mock, prover) = setup(|cfg| {
+            cfg.proof_input_field = input_field.to_string();
+            cfg.extra_prove_fields = extra_fields.as_object().unwrap().clone();
+        })
+        .await;
+        mount_prove(&mock, "proof-1").await;
+
+        let resp = match options {
+            Some(options) => prover.prove_with_options(chunk_request(), options).await.0,
+            None => prover.prove(chunk_request()).await,
+        };
+        assert_eq!(resp.task_id, "proof-1");
+
+        let requests = mock.requests().await;
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body, expected);
+    }
+}
+
+// Verify that with the `tracing` feature, a prove call runs in a span carrying its task id and
+// emits events for the request sent to Sindri.
+#[cfg(feature = "tracing")]
+#[tokio::test]
+async fn test_mock_tracing_spans() {
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let (mock, prover) = setup(|_| {}).await;
+    mount_prove(&mock, "proof-1").await;
+
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+    prover.prove(chunk_request()).await;
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("sindri.prove{"), "{output}");
+    assert!(output.contains("circuit_type=Chunk"), "{output}");
+    assert!(output.contains("request sent"), "{output}");
+    assert!(output.contains("response received"), "{output}");
+    assert!(output.contains("task_id=\"proof-1\""), "{output}");
+}
+
+// Verify that a proof submitted with a reference is tagged with it and can be resolved by it,
+// picking the most recent proof when the reference was submitted more than once.
+#[tokio::test]
+async fn test_mock_query_by_reference() {
+    let (mock, prover) = setup(|_| {}).await;
+    mount_prove(&mock, "proof-2").await;
+    mock.mock_times(
+        "POST",
+        "/api/v1/proof/list",
+        1,
+        json_response(serde_json::json!({ "count": 0, "items": [] })),
+    )
+    .await;
+    let mut older = proof_info("proof-1", "Failed");
+    older["date_created"] = "2024-10-29T12:34:56.789Z".into();
+    mock.mock_times(
+        "POST",
+        "/api/v1/proof/list",
+        1,
+        json_response(serde_json::json!({
+            "count": 2,
+            "items": [older, proof_info("proof-2", "Ready")],
+        })),
+    )
+    .await;
+    mount_status(&mock, "proof-2", "Ready").await;
+
+    assert!(matches!(
+        prover.query_by_reference("chunk-42").await,
+        Err(CloudProverError::UnknownReference { reference }) if reference == "chunk-42"
+    ));
+
+    let (resp, _) = prover
+        .prove_with_reference(chunk_request(), "chunk-42")
+        .await;
+    assert_eq!(resp.task_id, "proof-2");
+    let resp = prover.query_by_reference("chunk-42").await.unwrap();
+    assert_eq!(resp.task_id, "proof-2");
+    assert!(matches!(resp.status, TaskStatus::Success));
+
+    let requests = mock.requests().await;
+    let prove_body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+    assert_eq!(
+        prove_body["meta"],
+        serde_json::json!({ "reference": "chunk-42" })
+    );
+    let list_filter: serde_json::Value = serde_json::from_slice(&requests[2].body).unwrap();
+    assert_eq!(
+        list_filter["meta"],
+        serde_json::json!({ "reference": "chunk-42" })
+    );
+}
+
+// Verify that every retry of a submission carries the same idempotency key and separate
+// submissions different ones, and that within the dedup window resubmitting the same input
+// returns the earlier task without starting another proof.
+#[tokio::test]
+async fn test_mock_prove_dedup() {
+    for (dedup_window_sec, resubmitted_task_id, posts) in
+        [(None, "proof-2", 3), (Some(60), "proof-1", 2)]
+    {
+        let (mock, prover) = setup(|cfg| cfg.prove_dedup_window_sec = dedup_window_sec).await;
+        mock.mock_times("POST", &prove_path(), 1, ResponseTemplate::new(500))
+            .await;
+        mock.mock_times(
+            "POST",
+            &prove_path(),
+            1,
+            json_response(proof_info("proof-1", "Queued")),
+        )
+        .await;
+        mount_prove(&mock, "proof-2").await;
+
+        assert_eq!(prover.prove(chunk_request()).await.task_id, "proof-1");
+        let resp = prover.prove(chunk_request()).await;
+        assert_eq!(resp.task_id, resubmitted_task_id);
+        assert!(resp.error.is_none());
+
+        let keys: Vec<String> = mock
+            .requests()
+            .await
+            .iter()
+            .map(|req| req.headers["idempotency-key"].to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(keys.len(), posts);
+        assert_eq!(keys[0], keys[1]);
+        if posts == 3 {
+            assert_ne!(keys[1], keys[2]);
+        }
+
+        // A different input is always submitted.
+        let req = ProveRequest {
+            input: "[{}, {}]".to_string(),
+            ..chunk_request()
+        };
+        assert_eq!(prover.prove(req).await.task_id, "proof-2");
+        assert_eq!(mock.received_requests().await, posts + 1);
+    }
+}
+
+// Verify that identical submissions made while one is in flight wait for it and share its task,
+// without echoing the input unless asked to, and that one of them is sent when it fails.
+#[tokio::test]
+async fn test_mock_prove_dedup_in_flight() {
+    let delay = Duration::from_millis(300);
+    for (first, posts) in [(200, 1), (400, 2)] {
+        let (mock, prover) = setup(|cfg| cfg.prove_dedup_window_sec = Some(60)).await;
+        let response = match first {
+            200 => json_response(proof_info("proof-1", "Queued")),
+            status => ResponseTemplate::new(status),
+        };
+        mock.mock_times("POST", &prove_path(), 1, response.set_delay(delay))
+            .await;
+        mount_prove(&mock, "proof-1").await;
+
+        let resps = prover
+            .prove_many((0..3).map(|_| chunk_request()).collect(), 3)
+            .await;
+        if first == 200 {
+            assert!(resps[0].error.is_none());
+        } else {
+            assert!(resps[0].error.is_some());
+        }
+        for resp in &resps[1..] {
+            assert_eq!(resp.task_id, "proof-1");
+            assert!(resp.error.is_none());
+        }
+        // Only a submission that was sent echoes its input; which waiting one takes over from a
+        // failed submission is up to the scheduler.
+        let sent = resps[1..]
+            .iter()
+            .filter(|resp| resp.input.is_some())
+            .count();
+        assert_eq!(sent, posts - 1);
+        assert_eq!(mock.received_requests().await, posts);
+    }
+}
+
+// Verify that listing proofs follows pagination from the given offset, asking for no more than the
+// limit leaves and cutting short a page longer than asked for, and merges the pages in order.
+#[tokio::test]
+async fn test_mock_list_proofs() {
+    let page = |ids: std::ops::Range<usize>, count: usize| {
+        let items: Vec<_> = ids
+            .map(|i| proof_info(&format!("proof-{i}"), "Ready"))
+            .collect();
+        json_response(serde_json::json!({ "count": count, "items": items }))
+    };
+    for (limit, offset, pages, expected_pages, expected_ids) in [
+        // Follows pagination until the listing is exhausted.
+        (
+            None,
+            0,
+            vec![page(0..100, 130), page(100..130, 130)],
+            vec![("100", "0"), ("100", "100")],
+            0..130,
+        ),
+        // The second page asks only for what is left of the limit, but gets more.
+        (
+            Some(150),
+            10,
+            vec![page(10..110, 300), page(110..170, 300)],
+            vec![("100", "10"), ("50", "110")],
+            10..160,
+        ),
+        // The first page already holds more than the limit.
+        (Some(5), 0, vec![page(0..8, 300)], vec![("5", "0")], 0..5),
+    ] {
+        let (mock, prover) = setup(|_| {}).await;
+        for response in pages {
+            mock.mock_times("POST", "/api/v1/proof/list", 1, response)
+                .await;
+        }
+
+        let proofs = prover
+            .list_proofs(ListProofsParams {
+                limit,
+                offset,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let ids: Vec<String> = proofs.iter().map(|p| p.task_id().to_string()).collect();
+        let expected: Vec<String> = expected_ids.map(|i| format!("proof-{i}")).collect();
+        assert_eq!(ids, expected);
+
+        let requested: Vec<(String, String)> = mock
+            .requests()
+            .await
+            .iter()
+            .map(|req| {
+                let params = query_params(req);
+                (params["limit"].clone(), params["offset"].clone())
+            })
+            .collect();
+        let expected_pages: Vec<(String, String)> = expected_pages
+            .into_iter()
+            .map(|(limit, offset)| (limit.to_string(), offset.to_string()))
+            .collect();
+        assert_eq!(
+            requested, expected_pages,
+            "limit {limit:?}, offset {offset}"
+        );
+    }
+}
+
+// Verify that the estimate averages the timing of recent successful proofs and reports the number
+// of queued proofs.
+#[tokio::test]
+async fn test_mock_estimate() {
+    let (mock, prover) = setup(|_| {}).await;
+    let mut slow = proof_info("proof-2", "Ready");
+    slow["compute_time_sec"] = 17.5.into();
+    slow["queue_time_sec"] = 2.5.into();
+    mock.mock_times(
+        "POST",
+        "/api/v1/proof/list",
+        1,
+        json_response(serde_json::json!({
+            "count": 2,
+            "items": [proof_info("proof-1", "Ready"), slow],
+        })),
+    )
+    .await;
+    mock.mock_times(
+        "POST",
+        "/api/v1/proof/list",
+        1,
+        json_response(serde_json::json!({
+            "count": 7,
+            "items": [proof_info("proof-3", "Queued")],
+        })),
+    )
+    .await;
+
+    let estimate = prover.estimate(CircuitType::Chunk).await.unwrap();
+    assert_eq!(
+        estimate,
+        EstimateInfo {
+            sample_size: 2,
+            avg_compute_time_sec: Some(15.0),
+            avg_queue_time_sec: Some(2.0),
+            queued: 7,
+        }
+    );
+
+    let requests = mock.requests().await;
+    let filters: Vec<serde_json::Value> = requests
+        .iter()
+        .map(|req| serde_json::from_slice(&req.body).unwrap())
+        .collect();
+    assert_eq!(filters[0]["status"], "Ready");
+    assert_eq!(filters[1]["status"], "Queued");
+    assert_eq!(filters[0]["circuit_id"], "scroll-tech/chunk_prover:v0.13.1");
+}
+
+// Verify that `query_tasks` returns the responses in the order of the task ids, however quickly
+// each is answered.
+#[tokio::test]
+async fn test_mock_query_tasks() {
+    let (mock, prover) = setup(|_| {}).await;
+    for (task_id, status, delay_ms) in [
+        ("proof-1", "Ready", 300),
+        ("proof-2", "In Progress", 100),
+        ("proof-3", "Failed", 0),
+    ] {
+        mock.mock_proof_detail(
+            task_id,
+            json_response(proof_info(task_id, status)).set_delay(Duration::from_millis(delay_ms)),
+        )
+        .await;
+    }
+
+    let task_ids: Vec<String> = ["proof-1", "proof-2", "proof-3"]
+        .iter()
+        .map(|id| id.to_string())
+        .collect();
+    let responses = prover.query_tasks(&task_ids).await;
+    let ids: Vec<&str> = responses.iter().map(|r| r.task_id.as_str()).collect();
+    assert_eq!(ids, ["proof-1", "proof-2", "proof-3"]);
+    assert!(matches!(responses[0].status, TaskStatus::Success));
+    assert!(matches!(responses[1].status, TaskStatus::Proving));
+    assert!(matches!(responses[2].status, TaskStatus::Failed));
+    assert_eq!(mock.received_requests().await, 3);
+}
+
+// Verify that a submission answered with 202 Accepted is reported as queued even when the body
+// already claims the proof is ready, while a 200 OK keeps the body's status.
+#[tokio::test]
+async fn test_mock_prove_accepted() {
+    for (http_status, queued) in [(202, true), (200, false)] {
+        let (mock, prover) = setup(|_| {}).await;
+        mock.mock_prove(
+            CircuitType::Chunk,
+            CIRCUIT_VERSION,
+            ResponseTemplate::new(http_status).set_body_json(proof_info("proof-1", "Ready")),
+        )
+        .await;
+
+        let (resp, metadata) = prover.prove_with_metadata(chunk_request()).await;
+        assert_eq!(resp.task_id, "proof-1");
+        assert_eq!(matches!(resp.status, TaskStatus::Queued), queued);
+        assert_eq!(matches!(resp.status, TaskStatus::Success), !queued);
+        assert_eq!(metadata.status_detail.as_deref(), Some("Ready"));
+    }
+}
+
+// Verify that reserved characters in a task id are percent-encoded instead of altering the path.
+#[tokio::test]
+async fn test_mock_task_id_encoding() {
+    let (mock, prover) = setup(|_| {}).await;
+
+    for (task_id, path) in [
+        (
+            "../circuit/x?y#z",
+            "/api/v1/proof/..%2Fcircuit%2Fx%3Fy%23z/detail",
+        ),
+        ("a b%", "/api/v1/proof/a%20b%25/detail"),
+    ] {
+        query(&prover, task_id).await;
+        let requests = mock.requests().await;
+        let last = requests.last().unwrap();
+        assert_eq!(last.url.path(), path, "task id {task_id:?}");
+        assert_eq!(last.url.query_pairs().count(), 3);
+        assert_eq!(last.url.fragment(), None);
+    }
+
+    for task_id in ["", ".", ".."] {
+        let resp = query(&prover, task_id).await;
+        assert!(resp.error.unwrap().contains("path segment"));
+    }
+    assert_eq!(mock.received_requests().await, 2);
+}
+
+// Verify that `max_in_flight_requests` makes concurrent calls wait for each other.
+#[tokio::test]
+async fn test_mock_max_in_flight_requests() {
+    let (mock, prover) = setup(|cfg| cfg.max_in_flight_requests = Some(1)).await;
+    let task_ids: Vec<String> = (1..=3).map(|i| format!("proof-{i}")).collect();
+    for task_id in &task_ids {
+        mock.mock_proof_detail(
+            task_id,
+            json_response(proof_info(task_id, "Queued")).set_delay(Duration::from_millis(200)),
+        )
+        .await;
+    }
+
+    let start = std::time::Instant::now();
+    let responses = prover.query_tasks(&task_ids).await;
+    assert!(start.elapsed() >= Duration::from_millis(600));
+    assert!(responses.iter().all(|resp| resp.error.is_none()));
+
+    let mut cfg = test_config(&mock);
+    cfg.max_in_flight_requests = Some(0);
+    assert!(matches!(
+        CloudProver::try_new(cfg),
+        Err(CloudProverError::InvalidConfig(_))
+    ));
+}
+
+// Records the task status changes reported by the prover.
+#[derive(Default)]
+struct TransitionRecorder(std::sync::Mutex<Vec<(String, SindriTaskStatus, SindriTaskStatus)>>);
+
+impl MetricsRecorder for TransitionRecorder {
+    fn record_transition(
+        &self,
+        task_id: &str,
+        from: &SindriTaskStatus,
+        to: &SindriTaskStatus,
+        _elapsed: Duration,
+    ) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((task_id.to_string(), from.clone(), to.clone()));
+    }
+}
+
+// Verify that each change of a task's status is reported once, from submission to completion.
+#[tokio::test]
+async fn test_mock_task_transitions() {
+    let (mock, prover) = setup(|_| {}).await;
+    mount_prove(&mock, "proof-1").await;
+    for status in ["Queued", "In Progress", "In Progress"] {
+        mock.mock_times(
+            "GET",
+            &detail_path("proof-1"),
+            1,
+            json_response(proof_info("proof-1", status)),
+        )
+        .await;
+    }
+    mount_status(&mock, "proof-1", "Ready").await;
+    let recorder = Arc::new(TransitionRecorder::default());
+    let prover = prover.with_metrics(recorder.clone());
+
+    prover.prove(chunk_request()).await;
+    for _ in 0..5 {
+        query(&prover, "proof-1").await;
+    }
+
+    let transitions = recorder.0.lock().unwrap().clone();
+    assert_eq!(
+        transitions,
+        [
+            (
+                "proof-1".to_string(),
+                SindriTaskStatus::Queued,
+                SindriTaskStatus::Proving
+            ),
+            (
+                "proof-1".to_string(),
+                SindriTaskStatus::Proving,
+                SindriTaskStatus::Success
+            ),
+        ]
+    );
+}
+
+// Verify that a proof's vk is fetched without the proof and public inputs, and reformatted.
+#[tokio::test]
+async fn test_mock_get_proof_vk() {
+    let (mock, prover) = setup(|_| {}).await;
+    let mut with_vk = proof_info("proof-1", "Ready");
+    with_vk["verification_key"] = serde_json::json!({ "verification_key": "-_8" });
+    mock.mock_proof_detail("proof-1", json_response(with_vk))
+        .await;
+    mount_status(&mock, "proof-2", "Ready").await;
+
+    assert_eq!(prover.get_proof_vk("proof-1").await.unwrap(), "+/8=");
+    let params = query_params(&mock.requests().await[0]);
+    assert_eq!(params["include_verification_key"], "true");
+    assert_eq!(params["include_proof"], "false");
+    assert_eq!(params["include_public"], "false");
+
+    assert!(matches!(
+        prover.get_proof_vk("proof-2").await,
+        Err(CloudProverError::VkUnavailable { .. })
+    ));
+}
+
+// Verify that a bundle submission reports the hash of the unwrapped input it actually sent.
+#[tokio::test]
+async fn test_mock_prove_input_sha256() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_prove(
+        CircuitType::Bundle,
+        CIRCUIT_VERSION,
+        json_response(proof_info("proof-1", "Queued")),
+    )
+    .await;
+
+    let req = ProveRequest {
+        circuit_type: CircuitType::Bundle,
+        input: r#"{"batch_proofs": [{}]}"#.to_string(),
+        ..chunk_request()
+    };
+    let (_, metadata) = prover.prove_with_metadata(req).await;
+    let requests = mock.requests().await;
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["proof_input"], "[{}]");
+    assert_eq!(metadata.input_sha256, Some(input_sha256("[{}]")));
+}
+
+// Verify that shutting down waits for a submission in flight, gives up on one outlasting the grace
+// period, and that later requests fail either way.
+#[tokio::test]
+async fn test_mock_shutdown() {
+    for (delay, grace, drained) in [
+        (Duration::from_millis(300), Duration::from_secs(5), true),
+        (Duration::from_secs(2), Duration::from_millis(100), false),
+    ] {
+        let (mock, prover) = setup(|_| {}).await;
+        mock.mock_prove(
+            CircuitType::Chunk,
+            CIRCUIT_VERSION,
+            json_response(proof_info("proof-1", "Queued")).set_delay(delay),
+        )
+        .await;
+
+        let submission = tokio::spawn({
+            let prover = prover.clone();
+            async move { prover.prove(chunk_request()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(prover.shutdown(grace).await, drained);
+        if drained {
+            assert_eq!(submission.await.unwrap().task_id, "proof-1");
+        } else {
+            submission.abort();
+        }
+
+        let resp = prover.prove(chunk_request()).await;
+        assert!(resp
+            .error
+            .unwrap()
+            .contains(&CloudProverError::ShutDown.to_string()));
+        assert_eq!(mock.requests().await.len(), 1);
+    }
+}
+
+// Verify that an unlisted circuit version is rejected by default, and submitted as requested when
+// the version check is relaxed.
+#[tokio::test]
+async fn test_mock_strict_version_check() {
+    for strict_version_check in [true, false] {
+        let (mock, prover) = setup(|cfg| cfg.strict_version_check = strict_version_check).await;
+        mock.mock_prove(
+            CircuitType::Chunk,
+            "v0.14.0",
+            json_response(proof_info("proof-1", "Queued")),
+        )
+        .await;
+
+        let req = ProveRequest {
+            circuit_version: "v0.14.0".to_string(),
+            ..chunk_request()
+        };
+        let resp = prover.prove(req).await;
+        if strict_version_check {
+            assert!(matches!(resp.status, TaskStatus::Failed));
+            assert!(mock.requests().await.is_empty());
+        } else {
+            assert_eq!(resp.task_id, "proof-1");
+            assert_eq!(resp.circuit_version, "v0.14.0");
+        }
+    }
+}
+
+// Adds a header to every request, standing in for middleware of the caller's own.
+struct TagMiddleware;
+
+#[async_trait::async_trait]
+impl reqwest_middleware::Middleware for TagMiddleware {
+    async fn handle(
+        &self,
+        mut req: reqwest::Request,
+        extensions: &mut http::Extensions,
+        next: reqwest_middleware::Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        req.headers_mut()
+            .insert("x-tag", reqwest::header::HeaderValue::from_static("custom"));
+        next.run(req, extensions).await
+    }
+}
+
+// Verify that a caller-supplied client, with its own middleware, sends the prover's requests.
+#[tokio::test]
+async fn test_mock_with_client() {
+    let mock = MockSindri::start().await;
+    mount_status(&mock, "proof-1", "Queued").await;
+    let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(TagMiddleware)
+        .build();
+    let prover = CloudProver::with_client(test_config(&mock), client).unwrap();
+
+    let resp = query(&prover, "proof-1").await;
+    assert!(matches!(resp.status, TaskStatus::Queued));
+    let requests = mock.requests().await;
+    assert_eq!(requests[0].headers["x-tag"], "custom");
+    assert!(requests[0].headers.contains_key("authorization"));
+}
+
+// Verify that repeated failures open the circuit breaker, which refuses requests without sending
+// them, then lets a trial request through after the cooldown and closes when it succeeds.
+#[tokio::test]
+async fn test_mock_circuit_breaker() {
+    let (mock, prover) = setup(|cfg| {
+        cfg.retry_count = 0;
+        cfg.circuit_breaker_threshold = Some(2);
+        cfg.circuit_breaker_cooldown_sec = 1;
+    })
+    .await;
+    mock.mock_times(
+        "GET",
+        &detail_path("proof-1"),
+        2,
+        ResponseTemplate::new(503),
+    )
+    .await;
+    let mut with_vk = proof_info("proof-1", "Ready");
+    with_vk["verification_key"] = serde_json::json!({ "verification_key": "-_8" });
+    mock.mock_proof_detail("proof-1", json_response(with_vk))
+        .await;
+
+    for _ in 0..2 {
+        assert!(matches!(
+            prover.get_proof_vk("proof-1").await,
+            Err(CloudProverError::HttpStatus { .. })
+        ));
+    }
+    let refused = prover.get_proof_vk("proof-1").await;
+    assert!(matches!(refused, Err(CloudProverError::CircuitOpen { .. })));
+    assert!(refused.unwrap_err().is_retryable());
+    assert_eq!(mock.received_requests().await, 2);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(prover.get_proof_vk("proof-1").await.unwrap(), "+/8=");
+    assert_eq!(prover.get_proof_vk("proof-1").await.unwrap(), "+/8=");
+    assert_eq!(mock.received_requests().await, 4);
+}
+
+// Verify that the client-observed round trip of a query is reported alongside Sindri's timing.
+#[tokio::test]
+async fn test_mock_round_trip() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_proof_detail(
+        "proof-1",
+        json_response(proof_info("proof-1", "Ready")).set_delay(Duration::from_millis(200)),
+    )
+    .await;
+
+    let (resp, metadata) = prover
+        .query_task_with_metadata(query_request("proof-1"))
+        .await;
+    assert_eq!(resp.compute_time_sec, Some(12.5));
+    let round_trip = metadata.round_trip.unwrap();
+    assert!(round_trip >= Duration::from_millis(200), "{round_trip:?}");
+}
+
+// Verify that the rate limit of the latest response carrying rate-limit headers is exposed.
+#[tokio::test]
+async fn test_mock_rate_limit() {
+    let (mock, prover) = setup(|_| {}).await;
+    mock.mock_proof_detail(
+        "proof-1",
+        json_response(proof_info("proof-1", "Queued"))
+            .insert_header("x-ratelimit-limit", "100")
+            .insert_header("x-ratelimit-remaining", "42")
+            .insert_header("x-ratelimit-reset", "1700000000"),
+    )
+    .await;
+    mount_status(&mock, "proof-2", "Queued").await;
+    assert_eq!(prover.rate_limit(), None);
+
+    for task_id in ["proof-1", "proof-2"] {
+        query(&prover, task_id).await;
+    }
+    assert_eq!(
+        prover.rate_limit(),
+        Some(RateLimit {
+            limit: Some(100),
+            remaining: Some(42),
+            reset_at: Some(std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+        })
+    );
+}
+
+// Verify that `post_retry_count` limits the retries of submissions without affecting reads.
+#[tokio::test]
+async fn test_mock_post_retry_count() {
+    let (mock, prover) = setup(|cfg| {
+        cfg.retry_count = 1;
+        cfg.post_retry_count = Some(0);
+    })
+    .await;
+    mock.mock_prove(
+        CircuitType::Chunk,
+        CIRCUIT_VERSION,
+        ResponseTemplate::new(500),
+    )
+    .await;
+    mock.mock_proof_detail("proof-1", ResponseTemplate::new(500))
+        .await;
+
+    let prove = prover.prove(chunk_request()).await;
+    assert!(matches!(prove.status, TaskStatus::Failed));
+    assert_eq!(mock.received_requests().await, 1);
+
+    query(&prover, "proof-1").await;
+    assert_eq!(mock.received_requests().await, 3);
+    assert_eq!(prover.total_retries(), 1);
+}
+
+// Verify that a ready proof is written to the writer as Sindri sent it, and that an unfinished
+// task writes nothing.
+#[tokio::test]
+async fn test_mock_download_proof() {
+    let (mock, prover) = setup(|_| {}).await;
+    mount_status(&mock, "proof-1", "Ready").await;
+    mount_status(&mock, "proof-2", "In Progress").await;
+
+    let mut proof = Vec::new();
+    prover.download_proof("proof-1", &mut proof).await.unwrap();
+    assert_eq!(proof, br#"{"proof":"AAAA"}"#);
+    let params = query_params(&mock.requests().await[0]);
+    assert_eq!(params["include_proof"], "true");
+    assert_eq!(params["include_public"], "false");
+
+    let mut proof = Vec::new();
+    assert!(matches!(
+        prover.download_proof("proof-2", &mut proof).await,
+        Err(CloudProverError::ProofUnavailable { .. })
+    ));
+    assert!(proof.is_empty());
+}
+
+// Verify that a compute time reported while proving is not passed off as the final one.
+#[tokio::test]
+async fn test_mock_compute_time_while_proving() {
+    let (mock, prover) = setup(|_| {}).await;
+    let mut proving = proof_info("proof-1", "In Progress");
+    proving["compute_time_sec"] = serde_json::json!(3.0);
+    mock.mock_proof_detail("proof-1", json_response(proving))
+        .await;
+    mount_status(&mock, "proof-2", "Ready").await;
+
+    let (resp, metadata) = prover
+        .query_task_with_metadata(query_request("proof-1"))
+        .await;
+    assert!(matches!(resp.status, TaskStatus::Proving));
+    assert_eq!(resp.compute_time_sec, None);
+    assert_eq!(resp.finished_at, None);
+    assert!(resp.started_at.is_some());
+    assert_eq!(metadata.compute_time_sec, Some(3.0));
+
+    let (resp, _) = prover
+        .query_task_with_metadata(query_request("proof-2"))
+        .await;
+    assert!(matches!(resp.status, TaskStatus::Success));
+    assert_eq!(resp.compute_time_sec, Some(12.5));
+    assert!(resp.finished_at.is_some());
+}
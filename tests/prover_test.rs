@@ -0,0 +1,275 @@
+use scroll_proving_sdk::prover::{proving_service::TaskStatus, CircuitType};
+use sindri_scroll_sdk::error::CloudProverError;
+use sindri_scroll_sdk::prover::{
+    circuit_slug, input_sha256, parse_circuit_slug, reformat_vk, reformat_vk_to_sindri,
+    reprocess_prove_input, validate_prove_input, FailureCategory, ProofFailure, RateLimit,
+    SindriProofInfoResponse, SindriTaskStatus, TaskMetadata,
+};
+use sindri_scroll_sdk::utils::from_str_unbounded;
+
+// Verify that each defined circuit type maps onto the circuit name hosted by Sindri.
+#[test]
+fn test_circuit_slug() {
+    assert_eq!(circuit_slug(CircuitType::Chunk).unwrap(), "chunk_prover");
+    assert_eq!(circuit_slug(CircuitType::Batch).unwrap(), "batch_prover");
+    assert_eq!(circuit_slug(CircuitType::Bundle).unwrap(), "bundle_prover");
+}
+
+// Verify that an undefined circuit type is reported as an error rather than a panic.
+#[test]
+fn test_circuit_slug_undefined() {
+    assert!(matches!(
+        circuit_slug(CircuitType::Undefined),
+        Err(CloudProverError::UndefinedCircuitType)
+    ));
+}
+
+// Verify that well-formed inputs for each circuit type pass validation.
+#[test]
+fn test_validate_prove_input() {
+    validate_prove_input(CircuitType::Chunk, r#"[{"header": {}}]"#).unwrap();
+    validate_prove_input(
+        CircuitType::Batch,
+        r#"{"chunk_infos": [], "chunk_proofs": [], "batch_header": {}}"#,
+    )
+    .unwrap();
+    validate_prove_input(CircuitType::Bundle, r#"{"batch_proofs": []}"#).unwrap();
+}
+
+// Verify that malformed or wrongly shaped inputs are rejected before submission.
+#[test]
+fn test_validate_prove_input_rejects_malformed() {
+    assert!(validate_prove_input(CircuitType::Chunk, "not json").is_err());
+    assert!(validate_prove_input(CircuitType::Chunk, "[]").is_err());
+    assert!(validate_prove_input(CircuitType::Batch, r#"{"chunk_proofs": []}"#).is_err());
+    assert!(validate_prove_input(CircuitType::Bundle, r#"[]"#).is_err());
+    assert!(validate_prove_input(CircuitType::Undefined, r#"{}"#).is_err());
+}
+
+// Verify that input hashes are hex-encoded SHA-256 digests.
+#[test]
+fn test_input_sha256() {
+    assert_eq!(
+        input_sha256("abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+// Verify that chunk inputs are submitted unchanged.
+#[test]
+fn test_reprocess_prove_input_chunk() {
+    let input = r#"[{"header": {"number": "0x1"}}]"#;
+    assert_eq!(
+        reprocess_prove_input(CircuitType::Chunk, input).unwrap(),
+        input
+    );
+}
+
+// Verify that batch inputs are submitted unchanged.
+#[test]
+fn test_reprocess_prove_input_batch() {
+    let input = r#"{"chunk_infos": [], "chunk_proofs": [], "batch_header": {}}"#;
+    assert_eq!(
+        reprocess_prove_input(CircuitType::Batch, input).unwrap(),
+        input
+    );
+}
+
+// Verify that bundle inputs are unwrapped to the inner `batch_proofs` array.
+#[test]
+fn test_reprocess_prove_input_bundle() {
+    let output = reprocess_prove_input(CircuitType::Bundle, r#"{"batch_proofs": []}"#).unwrap();
+    assert_eq!(output, "[]");
+}
+
+// Verify that an undefined circuit type is rejected instead of passed through.
+#[test]
+fn test_reprocess_prove_input_undefined() {
+    assert!(reprocess_prove_input(CircuitType::Undefined, "{}").is_err());
+}
+
+// Verify that the public inputs of a captured Sindri proof detail response survive
+// deserialization and are surfaced through the task metadata, along with the proof id.
+#[test]
+fn test_proof_detail_public_inputs() {
+    let body = std::fs::read_to_string("tests/test_data/proof_detail_response.json").unwrap();
+    let resp: SindriProofInfoResponse = serde_json::from_str(&body).unwrap();
+    let expected = serde_json::json!({ "instances": ["0x01", "0x02"] });
+    assert_eq!(resp.public, Some(expected.clone()));
+
+    let metadata = TaskMetadata::from_response(&resp);
+    assert_eq!(metadata.public, Some(expected));
+    assert_eq!(metadata.proof_id.as_deref(), Some(resp.proof_id.as_str()));
+    assert_eq!(resp.task_id(), resp.proof_id);
+    assert_eq!(metadata.queue_time_sec, Some(4.25));
+}
+
+// Verify that the proof is passed on with the exact bytes Sindri sent, rather than re-serialized.
+#[test]
+fn test_proof_detail_raw_proof() {
+    let body = std::fs::read_to_string("tests/test_data/proof_detail_response.json").unwrap();
+    let resp: SindriProofInfoResponse = from_str_unbounded(&body).unwrap();
+    assert_eq!(
+        serde_json::to_string(&resp.proof).unwrap(),
+        r#"{"proof":"AAAA","instances":[1.50,2e3],"a":0}"#
+    );
+}
+
+// Verify that structured and plain Sindri failure messages are classified.
+#[test]
+fn test_proof_failure_from_error() {
+    let failure = ProofFailure::from_error(
+        r#"{"code": "INVALID_INPUT", "detail": "chunk trace is missing field `header`"}"#,
+    );
+    assert_eq!(failure.category, FailureCategory::Input);
+    assert_eq!(failure.message, "chunk trace is missing field `header`");
+    assert!(!failure.is_retryable());
+
+    let failure = ProofFailure::from_error("Prover was killed: out of memory");
+    assert_eq!(failure.category, FailureCategory::Transient);
+    assert_eq!(failure.message, "Prover was killed: out of memory");
+    assert!(failure.is_retryable());
+
+    let failure = ProofFailure::from_error("something went wrong");
+    assert_eq!(failure.category, FailureCategory::Unknown);
+}
+
+// Verify that converting a vk to Scroll's encoding and back yields the original Sindri encoding.
+#[test]
+fn test_reformat_vk_round_trip() {
+    // Lengths covering every padding case, and bytes that differ between the two alphabets.
+    for bytes in [
+        vec![],
+        vec![0xfb],
+        vec![0xfb, 0xff],
+        vec![0xfb, 0xff, 0xbf],
+        (0..=255).collect::<Vec<u8>>(),
+    ] {
+        let sindri_vk = base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD);
+        let scroll_vk = reformat_vk(sindri_vk.clone()).unwrap();
+        assert_eq!(scroll_vk, base64::encode_config(&bytes, base64::STANDARD));
+        assert_eq!(reformat_vk_to_sindri(scroll_vk).unwrap(), sindri_vk);
+    }
+}
+
+// Verify that a vk in the wrong encoding is reported as an error.
+#[test]
+fn test_reformat_vk_invalid() {
+    assert!(matches!(
+        reformat_vk("+/8=".to_string()),
+        Err(CloudProverError::VkReformat(_))
+    ));
+    assert!(matches!(
+        reformat_vk_to_sindri("-_8".to_string()),
+        Err(CloudProverError::VkReformat(_))
+    ));
+}
+
+// Verify that an unrecognized Sindri status is captured instead of failing deserialization.
+#[test]
+fn test_unknown_task_status() {
+    let body = std::fs::read_to_string("tests/test_data/proof_detail_response.json").unwrap();
+    let body = body.replace(r#""status": "Ready""#, r#""status": "Cancelled""#);
+    let resp: SindriProofInfoResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        resp.status,
+        SindriTaskStatus::Unknown("Cancelled".to_string())
+    );
+    assert!(matches!(TaskStatus::from(resp.status), TaskStatus::Failed));
+
+    // Known statuses keep their wire names in both directions.
+    let status: SindriTaskStatus = serde_json::from_str(r#""In Progress""#).unwrap();
+    assert_eq!(status, SindriTaskStatus::Proving);
+    assert_eq!(serde_json::to_string(&status).unwrap(), r#""In Progress""#);
+}
+
+// Verify that Sindri circuit identifiers are parsed back into circuit type and version.
+#[test]
+fn test_parse_circuit_slug() {
+    for (slug, circuit_type) in [
+        ("scroll-tech/chunk_prover:v0.13.1", CircuitType::Chunk),
+        ("scroll-tech/batch_prover:v0.13.1", CircuitType::Batch),
+        ("scroll-tech/bundle_prover:v0.13.1", CircuitType::Bundle),
+        ("bundle_prover:v0.13.1", CircuitType::Bundle),
+    ] {
+        assert_eq!(
+            parse_circuit_slug(slug).unwrap(),
+            (circuit_type, "v0.13.1".to_string())
+        );
+    }
+
+    for slug in [
+        "scroll-tech/chunk_prover",
+        "scroll-tech/chunk_prover:",
+        "scroll-tech/unknown_prover:v0.13.1",
+        "",
+    ] {
+        assert!(matches!(
+            parse_circuit_slug(slug),
+            Err(CloudProverError::InvalidCircuitSlug(_))
+        ));
+    }
+}
+
+// Verify that the Sindri status and failure reason survive the conversion to a `TaskStatus`.
+#[test]
+fn test_detailed_status() {
+    let body = std::fs::read_to_string("tests/test_data/proof_detail_response.json").unwrap();
+    let mut resp: SindriProofInfoResponse = serde_json::from_str(&body).unwrap();
+    let detailed = resp.detailed_status(true);
+    assert!(matches!(detailed.status, TaskStatus::Success));
+    assert_eq!(detailed.detail.as_deref(), Some("Ready"));
+
+    resp.proof = None;
+    let detailed = resp.detailed_status(true);
+    assert!(matches!(detailed.status, TaskStatus::Proving));
+    assert_eq!(
+        detailed.detail.as_deref(),
+        Some("Ready: proof not yet available")
+    );
+
+    resp.status = SindriTaskStatus::Failed;
+    resp.error = Some("Prover was killed: out of memory".to_string());
+    let detailed = resp.detailed_status(true);
+    assert!(matches!(detailed.status, TaskStatus::Failed));
+    assert_eq!(
+        detailed.detail.as_deref(),
+        Some("Failed: Prover was killed: out of memory")
+    );
+    assert_eq!(
+        TaskMetadata::from_response(&resp).status_detail,
+        detailed.detail
+    );
+
+    resp.status = SindriTaskStatus::Unknown("Cancelled".to_string());
+    resp.error = None;
+    let detailed = resp.detailed_status(true);
+    assert!(matches!(detailed.status, TaskStatus::Failed));
+    assert_eq!(detailed.detail.as_deref(), Some("Cancelled"));
+}
+
+// Verify that rate-limit resets are read as delays or timestamps depending on their size.
+#[test]
+fn test_rate_limit_from_headers() {
+    let mut headers = reqwest::header::HeaderMap::new();
+    assert_eq!(RateLimit::from_headers(&headers), None);
+
+    headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+    headers.insert("x-ratelimit-reset", "30".parse().unwrap());
+    let before = std::time::SystemTime::now();
+    let rate_limit = RateLimit::from_headers(&headers).unwrap();
+    assert_eq!(rate_limit.limit, None);
+    assert_eq!(rate_limit.remaining, Some(0));
+    let reset_in = rate_limit.reset_at.unwrap().duration_since(before).unwrap();
+    assert!(reset_in >= std::time::Duration::from_secs(30));
+    assert!(reset_in < std::time::Duration::from_secs(31));
+
+    headers.insert("x-ratelimit-remaining", "many".parse().unwrap());
+    headers.insert("x-ratelimit-reset", "1700000000".parse().unwrap());
+    let rate_limit = RateLimit::from_headers(&headers).unwrap();
+    assert_eq!(rate_limit.remaining, None);
+    assert_eq!(
+        rate_limit.reset_at,
+        Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000))
+    );
+}
@@ -1,6 +1,10 @@
 use chrono::{DateTime, Utc};
+use sindri_scroll_sdk::error::CloudProverError;
 use sindri_scroll_sdk::prover::{SindriProofInfoResponse, SindriTaskStatus};
-use sindri_scroll_sdk::utils::proving_timestamps_from_response;
+use sindri_scroll_sdk::utils::{
+    expand_env_vars, from_reader_unbounded, parse_timestamp, proving_timestamps_from_response,
+    ProvingStats,
+};
 
 // Verify results for `created_at`, `started_at`, and `finished_at` when the
 // response contains valid values.
@@ -10,11 +14,14 @@ fn test_proving_timestamps_from_response() {
     let date_str: &str = "2024-10-30T12:34:56.000Z"; // intentionally does not include fractional seconds
     let queue_time_sec: f64 = 5.0;
     let response: SindriProofInfoResponse = SindriProofInfoResponse {
+        circuit_name: None,
+        meta: None,
         compute_time_sec: Some(compute_time_sec),
         date_created: date_str.to_string(),
         error: None,
         proof: None,
         proof_id: "proof_id".to_string(),
+        public: None,
         queue_time_sec: Some(queue_time_sec),
         status: SindriTaskStatus::Success,
 
@@ -26,7 +33,13 @@ fn test_proving_timestamps_from_response() {
     let create_time_timestamp: f64 = create_time.timestamp() as f64; // number of second in the original timestamp
 
     // We expect three timestamps (seconds since the epoch).
-    let (created_at, started_at, finished_at) = proving_timestamps_from_response(&response);
+    let ProvingStats {
+        created_at,
+        started_at,
+        finished_at,
+        queue_time_sec: queue,
+        compute_time_sec: compute,
+    } = proving_timestamps_from_response("[Sindri client]", &response);
 
     assert_eq!(created_at, create_time_timestamp);
     assert_eq!(started_at.unwrap(), create_time_timestamp + queue_time_sec);
@@ -34,6 +47,8 @@ fn test_proving_timestamps_from_response() {
         finished_at.unwrap(),
         create_time_timestamp + queue_time_sec + compute_time_sec
     );
+    assert_eq!(queue, Some(queue_time_sec));
+    assert_eq!(compute, Some(compute_time_sec));
 }
 
 // Verify results for `created_at`, `started_at`, and `finished_at` when the
@@ -44,11 +59,14 @@ fn test_proving_timestamps_from_response_invalid_datestring() {
     let date_str: &str = "2024-10-30:12:34:56.000Z"; // malformed date string
     let queue_time_sec: f64 = 5.0;
     let response: SindriProofInfoResponse = SindriProofInfoResponse {
+        circuit_name: None,
+        meta: None,
         compute_time_sec: Some(compute_time_sec),
         date_created: date_str.to_string(),
         error: None,
         proof: None,
         proof_id: "proof_id".to_string(),
+        public: None,
         queue_time_sec: Some(queue_time_sec),
         status: SindriTaskStatus::Success,
 
@@ -56,9 +74,101 @@ fn test_proving_timestamps_from_response_invalid_datestring() {
     };
 
     // We expect no valid values.
-    let (created_at, started_at, finished_at) = proving_timestamps_from_response(&response);
+    let ProvingStats {
+        created_at,
+        started_at,
+        finished_at,
+        queue_time_sec: queue,
+        compute_time_sec: compute,
+    } = proving_timestamps_from_response("[Sindri client]", &response);
 
     assert_eq!(created_at, 0.0);
     assert_eq!(started_at, None);
     assert_eq!(finished_at, None);
+    // The durations do not depend on the date and are still reported.
+    assert_eq!(queue, Some(queue_time_sec));
+    assert_eq!(compute, Some(compute_time_sec));
+}
+
+// Verify that JSON nested far beyond serde_json's default recursion limit can be streamed in.
+#[test]
+fn test_from_reader_unbounded_deep_nesting() {
+    let depth = 1000;
+    let json = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+    assert!(serde_json::from_reader::<_, serde_json::Value>(json.as_bytes()).is_err());
+    let value: serde_json::Value = from_reader_unbounded(json.as_bytes()).unwrap();
+    assert_eq!(serde_json::to_string(&value).unwrap(), json);
+}
+
+// Verify that Sindri timestamps are parsed from RFC 3339 strings, including the form found in
+// real proof detail responses, and from epoch seconds.
+#[test]
+fn test_parse_timestamp() {
+    let body = std::fs::read_to_string("tests/test_data/proof_detail_response.json").unwrap();
+    let resp: SindriProofInfoResponse = serde_json::from_str(&body).unwrap();
+    let expected: DateTime<Utc> = "2024-10-30T12:34:56.789Z".parse().unwrap();
+    assert_eq!(resp.created_at().unwrap(), expected);
+
+    assert_eq!(
+        parse_timestamp("2024-10-30T12:34:56.789+00:00").unwrap(),
+        expected
+    );
+    assert_eq!(parse_timestamp("1730291696.789").unwrap(), expected);
+
+    // Epoch seconds sent as a JSON number are accepted too.
+    let body = body.replace(
+        r#""date_created": "2024-10-30T12:34:56.789Z""#,
+        r#""date_created": 1730291696"#,
+    );
+    let resp: SindriProofInfoResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(resp.created_at().unwrap().timestamp(), 1730291696);
+
+    // A fraction rounding up to a whole second carries into the seconds, and times before the
+    // epoch are not truncated towards it.
+    assert_eq!(
+        parse_timestamp("1700000000.9999996").unwrap(),
+        DateTime::from_timestamp(1700000001, 0).unwrap()
+    );
+    assert_eq!(
+        parse_timestamp("-1.5").unwrap(),
+        DateTime::from_timestamp(-2, 500_000_000).unwrap()
+    );
+
+    for value in ["2024-10-30:12:34:56.000Z", "", "NaN"] {
+        assert!(matches!(
+            parse_timestamp(value),
+            Err(CloudProverError::InvalidTimestamp(_))
+        ));
+    }
+}
+
+// Verify that `${VAR}` references are expanded, other dollar signs are kept, and undefined or
+// malformed references are rejected.
+#[test]
+fn test_expand_env_vars() {
+    temp_env::with_vars(
+        [
+            ("SINDRI_TEST_HOST", Some("sindri.app")),
+            ("SINDRI_TEST_UNSET", None),
+        ],
+        || {
+            assert_eq!(
+                expand_env_vars("https://${SINDRI_TEST_HOST}/v1").unwrap(),
+                "https://sindri.app/v1"
+            );
+            assert_eq!(
+                expand_env_vars("${SINDRI_TEST_HOST}${SINDRI_TEST_HOST}").unwrap(),
+                "sindri.appsindri.app"
+            );
+            assert_eq!(expand_env_vars("pa$$word $HOME").unwrap(), "pa$$word $HOME");
+
+            for value in ["${SINDRI_TEST_UNSET}", "${SINDRI_TEST_HOST", "${1X}", "${}"] {
+                assert!(matches!(
+                    expand_env_vars(value),
+                    Err(CloudProverError::InvalidConfig(_))
+                ));
+            }
+        },
+    );
 }
@@ -0,0 +1,83 @@
+use scroll_proving_sdk::prover::CircuitType;
+use serde_json::json;
+use sindri_scroll_sdk::error::CloudProverError;
+use sindri_scroll_sdk::prover::{reformat_vk, reformat_vk_to_sindri};
+use sindri_scroll_sdk::verifier::check_proof;
+
+// A proof in the shape Scroll's prover serializes, made for the vk `[0, 1, 2]` with the
+// instances 1 and 2.  Only the fields read by `check_proof` are real.
+fn proof() -> String {
+    let mut instances = vec![0u8; 64];
+    instances[31] = 1;
+    instances[63] = 2;
+    json!({
+        "protocol": "AAAA",
+        "proof": "AAAA",
+        "instances": base64::encode_config(instances, base64::STANDARD),
+        "vk": base64::encode_config([0, 1, 2], base64::STANDARD),
+    })
+    .to_string()
+}
+
+fn scroll_vk(raw: &[u8]) -> String {
+    base64::encode_config(raw, base64::STANDARD)
+}
+
+// Verify that a proof matches a vk passed through Sindri's encoding and back, with or without
+// matching public inputs, but not another vk or other public inputs.
+#[test]
+fn test_check_proof_round_trip() {
+    let vk = reformat_vk(reformat_vk_to_sindri(scroll_vk(&[0, 1, 2])).unwrap()).unwrap();
+    let public = json!({ "instances": ["0x1", "0x02"] });
+    for circuit_type in [CircuitType::Chunk, CircuitType::Batch] {
+        for public in [None, Some(&public)] {
+            assert!(check_proof(circuit_type, &proof(), &vk, public).unwrap());
+        }
+    }
+
+    for (vk, instances) in [
+        (scroll_vk(&[0, 1, 3]), json!(["0x01", "0x02"])),
+        (vk.clone(), json!(["0x01", "0x03"])),
+        (vk.clone(), json!(["0x1"])),
+        (vk.clone(), json!(["0x01", "0x02", "0x03"])),
+    ] {
+        let public = json!({ "instances": instances });
+        assert!(!check_proof(CircuitType::Chunk, &proof(), &vk, Some(&public)).unwrap());
+    }
+}
+
+// Verify that proofs which cannot be checked at all are reported as errors rather than as not
+// matching.
+#[test]
+fn test_check_proof_errors() {
+    let vk = scroll_vk(&[0, 1, 2]);
+    let verify = |circuit_type, proof: &str, vk: &str, public: Option<&serde_json::Value>| {
+        check_proof(circuit_type, proof, vk, public).unwrap_err()
+    };
+
+    assert!(matches!(
+        verify(CircuitType::Bundle, &proof(), &vk, None),
+        CloudProverError::LocalVerification(_)
+    ));
+    assert!(matches!(
+        verify(CircuitType::Undefined, &proof(), &vk, None),
+        CloudProverError::UndefinedCircuitType
+    ));
+    assert!(matches!(
+        verify(CircuitType::Chunk, &proof(), "AAE-", None),
+        CloudProverError::VkReformat(_)
+    ));
+    assert!(matches!(
+        verify(CircuitType::Chunk, "not a proof", &vk, None),
+        CloudProverError::Decode(_)
+    ));
+    assert!(matches!(
+        verify(
+            CircuitType::Chunk,
+            &proof(),
+            &vk,
+            Some(&json!({ "instances": [1, 2] }))
+        ),
+        CloudProverError::LocalVerification(_)
+    ));
+}